@@ -0,0 +1,145 @@
+// Persistent whatsmybitrate python workers for the development fallback.
+// Spawning python3 per file re-imports librosa/numpy every time; workers run the CLI's
+// `worker` mode and are reused across files instead.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// Lines of non-protocol output tolerated before a worker is considered broken
+const MAX_NOISE_LINES: usize = 50;
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Worker {
+    fn spawn(
+        python: &PathBuf,
+        script: &PathBuf,
+        envs: &HashMap<String, String>,
+    ) -> Result<Worker, String> {
+        let mut cmd = Command::new(python);
+        cmd.arg(script)
+            .arg("worker")
+            .envs(envs)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        #[cfg(target_os = "windows")]
+        {
+            let _ = cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to start analyzer worker: {}", e))?;
+        let stdin = child.stdin.take().ok_or("Analyzer worker has no stdin")?;
+        let stdout = child.stdout.take().ok_or("Analyzer worker has no stdout")?;
+        log::info!("[worker] Started analyzer worker (pid {})", child.id());
+        crate::processes::track(child.id());
+
+        Ok(Worker {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Send one request. The outer Err is a transport failure (worker is unusable);
+    /// the inner Result is the analyzer's own outcome.
+    fn request(&mut self, args: &[String]) -> Result<Result<serde_json::Value, String>, String> {
+        let line = serde_json::to_string(args).map_err(|e| e.to_string())?;
+        writeln!(self.stdin, "{}", line).map_err(|e| format!("worker write failed: {}", e))?;
+        self.stdin.flush().map_err(|e| format!("worker flush failed: {}", e))?;
+
+        for _ in 0..MAX_NOISE_LINES {
+            let mut response = String::new();
+            let n = self
+                .stdout
+                .read_line(&mut response)
+                .map_err(|e| format!("worker read failed: {}", e))?;
+            if n == 0 {
+                return Err("worker exited".to_string());
+            }
+
+            let Ok(json) = serde_json::from_str::<serde_json::Value>(response.trim()) else {
+                continue; // stray library output
+            };
+            let Some(exit) = json.get("exit").and_then(|v| v.as_i64()) else {
+                continue;
+            };
+            let result = json.get("result").cloned().unwrap_or(serde_json::Value::Null);
+
+            return Ok(if exit == 0 {
+                Ok(result)
+            } else {
+                Err(result
+                    .get("error")
+                    .and_then(|e| e.as_str())
+                    .unwrap_or("analyzer worker reported an error")
+                    .to_string())
+            });
+        }
+        Err("worker produced no response".to_string())
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        crate::processes::untrack(self.child.id());
+    }
+}
+
+fn idle_workers() -> &'static Mutex<Vec<Worker>> {
+    static IDLE: OnceLock<Mutex<Vec<Worker>>> = OnceLock::new();
+    IDLE.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Run one request on a pooled worker. Returns Err only for transport failures,
+/// in which case the caller should fall back to spawning a one-off process.
+pub fn request(
+    python: &PathBuf,
+    script: &PathBuf,
+    envs: &HashMap<String, String>,
+    args: &[String],
+) -> Result<Result<serde_json::Value, String>, String> {
+    let idle = idle_workers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pop();
+    let mut worker = match idle {
+        Some(w) => w,
+        None => Worker::spawn(python, script, envs)?,
+    };
+
+    match worker.request(args) {
+        Ok(outcome) => {
+            idle_workers()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(worker);
+            Ok(outcome)
+        }
+        // Dropping the worker kills it
+        Err(e) => Err(e),
+    }
+}
+
+/// Kill every idle worker (app shutdown)
+pub fn shutdown() {
+    idle_workers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
@@ -6,43 +6,20 @@ use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
-use tauri::Manager;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use tauri::{Emitter, Manager};
 
-use crate::types::{CacheEntry, ExtractedMetadata};
-use crate::cache::enforce_cache_limit;
+use crate::analyzer_worker;
+use crate::types::{
+    AnalyzerDiagnostics, AnalyzerWarming, CacheEntry, DependencyReport, ExtractedMetadata, ScanStatus, ScanWarning, ScanWarningCode,
+    SpectrogramOptions, StreamInfo, ToolStatus, ToolVersions, WindowAnalysis,
+};
+use crate::cache::{enforce_cache_limit, lock_cache};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
-/// Helper to resolve the absolute path of a bundled sidecar binary
-/// logic:
-/// 1. Check same directory as current executable (standard for Tauri bundled apps)
-/// 2. Check resource_dir/binaries/ (dev mode or alternative config)
-fn resolve_sidecar_path(app: &tauri::AppHandle, name: &str) -> Option<PathBuf> {
-    // 1. Check relative to executable (Contents/MacOS/ on Mac, or root of portable exe)
-    if let Ok(exe_path) = std::env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            let path = exe_dir.join(name);
-            log::error!("[sidecar] Checking exe_dir: {:?}", path);
-            if path.exists() {
-                return Some(path);
-            }
-        }
-    }
-
-    // 2. Check resource directory (standard dev layout or Windows sometimes)
-    if let Ok(resource_dir) = app.path().resource_dir() {
-        let path = resource_dir.join("binaries").join(name);
-        log::error!("[sidecar] Checking resource_dir: {:?}", path);
-        if path.exists() {
-            return Some(path);
-        }
-    }
-
-    None
-}
-
 /// Run ffprobe sidecar with given arguments, returns stdout as bytes
 /// Uses synchronous execution to avoid tokio runtime deadlocks
 pub fn run_ffprobe_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Vec<u8>, String> {
@@ -55,8 +32,8 @@ pub fn run_ffprobe_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Ve
     let binary_name = "ffprobe";
     
     // Try to find the bundled binary
-    if let Some(bundled_path) = resolve_sidecar_path(app, binary_name) {
-        log::error!("[ffprobe] Found bundled binary at {:?}, executing synchronously...", bundled_path);
+    if let Some(bundled_path) = resolve_resource(app, binary_name) {
+        log::debug!("[ffprobe] Found bundled binary at {:?}, executing synchronously...", bundled_path);
         
         let mut cmd = Command::new(&bundled_path);
         cmd.args(&args);
@@ -64,27 +41,27 @@ pub fn run_ffprobe_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Ve
         #[cfg(target_os = "windows")]
         cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
         
-        match cmd.output() {
+        match crate::processes::tracked_output(&mut cmd) {
             Ok(output) => {
                 if output.status.success() {
-                    log::error!("[ffprobe] Bundled ffprobe succeeded, stdout len: {}", output.stdout.len());
+                    log::debug!("[ffprobe] Bundled ffprobe succeeded, stdout len: {}", output.stdout.len());
                     return Ok(output.stdout);
                 } else {
-                    log::error!("[ffprobe] Bundled ffprobe failed: {}", String::from_utf8_lossy(&output.stderr));
+                    log::warn!("[ffprobe] Bundled ffprobe failed: {}", String::from_utf8_lossy(&output.stderr));
                     // Proceed to fallback
                 }
             },
             Err(e) => {
-                 log::error!("[ffprobe] Failed to execute bundled binary: {}", e);
+                 log::warn!("[ffprobe] Failed to execute bundled binary: {}", e);
                  // Proceed to fallback
             }
         }
     } else {
-        log::error!("[ffprobe] Bundled binary '{}' not found in standard locations", binary_name);
+        log::debug!("[ffprobe] Bundled binary '{}' not found in standard locations", binary_name);
     }
     
     // Fallback to system ffprobe (dev mode or if bundled binary not found/failed)
-    log::error!("[ffprobe] Falling back to system ffprobe");
+    log::debug!("[ffprobe] Falling back to system ffprobe");
     
     let mut cmd = Command::new("ffprobe");
     cmd.args(&args);
@@ -92,11 +69,11 @@ pub fn run_ffprobe_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Ve
     #[cfg(target_os = "windows")]
     cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     
-    let output = cmd.output()
+    let output = crate::processes::tracked_output(&mut cmd)
         .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
     
     if output.status.success() {
-        log::error!("[ffprobe] System ffprobe succeeded, stdout len: {}", output.stdout.len());
+        log::debug!("[ffprobe] System ffprobe succeeded, stdout len: {}", output.stdout.len());
         Ok(output.stdout)
     } else {
         let err = String::from_utf8_lossy(&output.stderr).to_string();
@@ -107,34 +84,264 @@ pub fn run_ffprobe_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Ve
 
 
 
-// Helper to get resource path, checking both root and 'resources' subdir
-pub fn get_resource_path(app: &tauri::AppHandle, name: &str) -> Option<PathBuf> {
-    let res_dir = app.path().resource_dir().ok()?;
-    
-    // 1. Check resources subdir (prod bundle often, and correctly placed in debug/resources)
-    // CRITICAL FIX: Check this FIRST because Tauri spills broken binaries to root target/debug in dev mode
-    let nested = res_dir.join("resources").join(name);
-    if nested.exists() {
-        return Some(nested);
+/// Run ffmpeg (bundled sidecar first, then the system one) with given arguments.
+/// Returns stderr as bytes, where ffmpeg writes its logs and filter reports.
+pub fn run_ffmpeg_sidecar(app: &tauri::AppHandle, args: Vec<&str>) -> Result<Vec<u8>, String> {
+    #[cfg(target_os = "windows")]
+    let binary_name = "ffmpeg.exe";
+    #[cfg(not(target_os = "windows"))]
+    let binary_name = "ffmpeg";
+
+    let program = resolve_resource(app, binary_name).unwrap_or_else(|| {
+        log::debug!("[ffmpeg] Bundled binary not found, falling back to system ffmpeg");
+        PathBuf::from("ffmpeg")
+    });
+    let mut cmd = Command::new(&program);
+    cmd.args(&args);
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = crate::processes::tracked_output(&mut cmd)
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if output.status.success() {
+        Ok(output.stderr)
+    } else {
+        let err = String::from_utf8_lossy(&output.stderr);
+        let last = err.lines().last().unwrap_or_default().trim().to_string();
+        log::error!("[ffmpeg] {:?} failed: {}", program, last);
+        Err(if last.is_empty() { "ffmpeg a échoué".to_string() } else { last })
     }
+}
+
+/// First line of `<program> -version`, or None if it cannot be run
+fn tool_version(program: &Path) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.arg("-version");
 
-    // 2. Check direct path (dev mode often, but can be broken binary)
-    let direct = res_dir.join(name);
-    if direct.exists() {
-        // For whatsmybitrate, we accept both file (onefile) and directory (onedir)
-        return Some(direct);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
     }
-    
-    // Check _up_/vendor (bundled ../vendor/whatsmybitrate often ends up here)
-    if name.contains("whatsmybitrate") {
-         let up_vendor = res_dir.join("_up_").join("vendor").join(name);
-         if up_vendor.exists() {
-             return Some(up_vendor);
-         }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+}
+
+/// Resolve an ffmpeg-suite tool the same way run_ffprobe_sidecar does:
+/// bundled sidecar first, then the system PATH
+fn check_ffmpeg_tool(app: &tauri::AppHandle, name: &str) -> ToolStatus {
+    #[cfg(target_os = "windows")]
+    let binary_name = format!("{}.exe", name);
+    #[cfg(not(target_os = "windows"))]
+    let binary_name = name.to_string();
+
+    if let Some(path) = resolve_resource(app, &binary_name) {
+        return ToolStatus {
+            found: true,
+            version: tool_version(&path),
+            path: Some(path.to_string_lossy().to_string()),
+        };
     }
-    
-    // Fallback to direct if neither found (or maybe let caller decide)
-    Some(direct) 
+
+    // System fallback: only counts as found if it actually runs
+    let version = tool_version(Path::new(name));
+    ToolStatus {
+        found: version.is_some(),
+        path: version.as_ref().map(|_| name.to_string()),
+        version,
+    }
+}
+
+/// Check that ffprobe, ffmpeg and whatsmybitrate can be resolved
+pub fn check_dependencies(app: &tauri::AppHandle) -> DependencyReport {
+    let mode = crate::settings::load_settings(app).analyzer_mode;
+    let whatsmybitrate = match resolve_analyzer(app, &mode) {
+        Ok(analyzer) => {
+            let path = analyzer.leading_args.first().unwrap_or(&analyzer.program);
+            (
+                ToolStatus {
+                    found: true,
+                    path: Some(path.to_string_lossy().to_string()),
+                    version: tool_versions(app).whatsmybitrate,
+                },
+                Some(analyzer.label),
+            )
+        }
+        Err(e) => {
+            log::warn!("[deps] whatsmybitrate not found: {}", e);
+            (
+                ToolStatus {
+                    found: false,
+                    path: None,
+                    version: None,
+                },
+                None,
+            )
+        }
+    };
+
+    DependencyReport {
+        ffprobe: check_ffmpeg_tool(app, "ffprobe"),
+        ffmpeg: check_ffmpeg_tool(app, "ffmpeg"),
+        whatsmybitrate: whatsmybitrate.0,
+        analyzer: whatsmybitrate.1,
+    }
+}
+
+/// Version of the bundled ffmpeg-suite tool, resolved like check_ffmpeg_tool
+fn ffmpeg_tool_version(app: &tauri::AppHandle, name: &str) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let binary_name = format!("{}.exe", name);
+    #[cfg(not(target_os = "windows"))]
+    let binary_name = name.to_string();
+
+    match resolve_resource(app, &binary_name) {
+        Some(path) => tool_version(&path),
+        None => tool_version(Path::new(name)),
+    }
+}
+
+/// Version reported by the analyzer's `version` mode
+fn query_analyzer_version(app: &tauri::AppHandle) -> Option<String> {
+    let mode = crate::settings::load_settings(app).analyzer_mode;
+    let analyzer = resolve_analyzer(app, &mode).ok()?;
+    match run_once(&analyzer, &["version".to_string()]) {
+        Ok(json) => json
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+        Err(e) => {
+            log::warn!("[whatsmybitrate] Could not read analyzer version: {}", e);
+            None
+        }
+    }
+}
+
+/// How long a failed analyzer version lookup stands before it is tried again
+const VERSION_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Versions found so far, and when the analyzer's was last asked for
+struct VersionCache {
+    versions: Option<ToolVersions>,
+    analyzer_attempt: Option<std::time::Instant>,
+}
+
+/// ffprobe, ffmpeg and whatsmybitrate versions, queried once per session since the
+/// bundled tools can't change while the app runs. A failed analyzer lookup is retried
+/// after VERSION_RETRY_AFTER, so cache entries don't stay unversioned for the session
+/// but a broken analyzer isn't spawned again for every file. The tools run outside
+/// the lock.
+pub fn tool_versions(app: &tauri::AppHandle) -> ToolVersions {
+    static VERSIONS: Mutex<VersionCache> = Mutex::new(VersionCache {
+        versions: None,
+        analyzer_attempt: None,
+    });
+    let lock = || VERSIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let known = {
+        let mut cache = lock();
+        let known = cache.versions.clone();
+        let due = match (&known, cache.analyzer_attempt) {
+            (Some(v), _) if v.whatsmybitrate.is_some() => false,
+            (_, Some(at)) => at.elapsed() >= VERSION_RETRY_AFTER,
+            (_, None) => true,
+        };
+        match known {
+            Some(known) if !due => return known,
+            // A retry is claimed before querying so the other threads don't repeat it;
+            // the very first lookup isn't, so no caller goes without versions
+            Some(_) => cache.analyzer_attempt = Some(std::time::Instant::now()),
+            None => {}
+        }
+        known
+    };
+
+    let versions = match known {
+        Some(known) => ToolVersions {
+            whatsmybitrate: query_analyzer_version(app),
+            ..known
+        },
+        None => ToolVersions {
+            ffprobe: ffmpeg_tool_version(app, "ffprobe"),
+            ffmpeg: ffmpeg_tool_version(app, "ffmpeg"),
+            whatsmybitrate: query_analyzer_version(app),
+        },
+    };
+    let mut cache = lock();
+    cache.analyzer_attempt = Some(std::time::Instant::now());
+    cache.versions = Some(versions.clone());
+    versions
+}
+
+/// Places a bundled resource can end up, in lookup order:
+/// 1. `resource_dir/resources/` (prod bundle, and correctly placed in debug/resources).
+///    Checked first because Tauri spills broken binaries to the root target/debug in dev.
+/// 2. `resource_dir/` directly (dev mode often, but can be a broken binary)
+/// 3. `resource_dir/_up_/vendor/` (where a bundled ../vendor/ ends up)
+/// 4. `exe_dir/../vendor/` (the vendor sources next to a dev build)
+/// 5. `exe_dir/` (where Tauri puts sidecars, e.g. Contents/MacOS/ in a mac bundle)
+/// 6. `resource_dir/binaries/` (sidecars in the dev layout)
+fn resource_candidates(res_dir: Option<&Path>, exe_dir: Option<&Path>, name: &str) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(res) = res_dir {
+        candidates.push(res.join("resources").join(name));
+        candidates.push(res.join(name));
+        candidates.push(res.join("_up_").join("vendor").join(name));
+    }
+    if let Some(exe) = exe_dir {
+        candidates.push(exe.join("..").join("vendor").join(name));
+        candidates.push(exe.join(name));
+    }
+    if let Some(res) = res_dir {
+        candidates.push(res.join("binaries").join(name));
+    }
+    candidates
+}
+
+/// First existing resource `name` (a file, or a directory for onedir builds)
+pub fn resolve_resource_in(res_dir: Option<&Path>, exe_dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+    resource_candidates(res_dir, exe_dir, name)
+        .into_iter()
+        .find(|p| p.exists())
+}
+
+/// First `file` inside any of the resource directories named `dir`
+pub fn resolve_resource_file_in(
+    res_dir: Option<&Path>,
+    exe_dir: Option<&Path>,
+    dir: &str,
+    file: &str,
+) -> Option<PathBuf> {
+    resource_candidates(res_dir, exe_dir, dir)
+        .into_iter()
+        .map(|d| d.join(file))
+        .find(|p| p.is_file())
+}
+
+fn resource_roots(app: &tauri::AppHandle) -> (Option<PathBuf>, Option<PathBuf>) {
+    let res_dir = app.path().resource_dir().ok();
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    (res_dir, exe_dir)
+}
+
+/// Resolve a bundled resource for this app, see resource_candidates for the order
+pub fn resolve_resource(app: &tauri::AppHandle, name: &str) -> Option<PathBuf> {
+    let (res_dir, exe_dir) = resource_roots(app);
+    resolve_resource_in(res_dir.as_deref(), exe_dir.as_deref(), name)
+}
+
+/// A file of the whatsmybitrate python sources (cli script, requirements.txt)
+pub fn resolve_vendor_file(app: &tauri::AppHandle, file: &str) -> Option<PathBuf> {
+    let (res_dir, exe_dir) = resource_roots(app);
+    resolve_resource_file_in(res_dir.as_deref(), exe_dir.as_deref(), "whatsmybitrate", file)
 }
 
 // Helper to get environment with bundled binaries in PATH
@@ -181,32 +388,83 @@ pub fn get_env_with_resources(app: &tauri::AppHandle) -> HashMap<String, String>
     envs
 }
 
-/// Check if a file is an audio file based on extension
-pub fn is_audio(path: &Path) -> bool {
-    match path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|s| s.to_lowercase())
-    {
-        Some(ext) => matches!(
-            ext.as_str(),
-            "mp3" | "m4a" | "aac" | "wav" | "flac" | "ogg" | "opus" | "webm"
-        ),
+/// Borrow a path as UTF-8 for subprocess arguments.
+/// Non-UTF8 paths are rejected explicitly instead of being passed as "" or mangled lossily.
+pub fn path_arg(path: &Path) -> Result<&str, String> {
+    path.to_str()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| format!("Unsupported path encoding: {}", path.display()))
+}
+
+/// Whether the file's extension is in `extensions` (the `audio_extensions` setting).
+/// Matching ignores case and a leading dot in the configured entries.
+pub fn is_audio(path: &Path, extensions: &[String]) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => extensions
+            .iter()
+            .any(|e| e.trim().trim_start_matches('.').eq_ignore_ascii_case(ext)),
         None => false,
     }
 }
 
-/// Calculate SHA256 hash of a file
+/// Audio files below this size are taken for stubs or leftovers, not real tracks
+pub const MIN_AUDIO_FILE_BYTES: u64 = 4 * 1024;
+
+/// Dotfiles (`.DS_Store`, AppleDouble `._track.mp3` resource forks, `.Trashes`...) and,
+/// on Windows, entries flagged hidden or system
+pub fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+        if let Ok(meta) = entry.metadata() {
+            return meta.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0;
+        }
+    }
+    false
+}
+
+/// Raised to stop the scan in progress. Hashing checks it between reads so a huge
+/// file doesn't hold the scan up.
+pub static SCAN_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Read size for hashing; large reads matter on multi-GB WAVs
+const HASH_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Calculate SHA256 hash of a file, without progress or cancellation
 pub fn file_hash(path: &Path) -> std::io::Result<String> {
+    file_hash_with_progress(path, None, None)
+}
+
+/// SHA256 of a file, adding the bytes read to `progress` as it goes and stopping with
+/// `ErrorKind::Interrupted` as soon as `cancel` is raised
+pub fn file_hash_with_progress(
+    path: &Path,
+    progress: Option<&AtomicU64>,
+    cancel: Option<&AtomicBool>,
+) -> std::io::Result<String> {
     let mut file = fs::File::open(path)?;
     let mut hasher = Sha256::new();
-    let mut buf = [0u8; 8192];
+    let mut buf = vec![0u8; HASH_BUFFER_BYTES];
     loop {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "hashing cancelled",
+            ));
+        }
         let n = file.read(&mut buf)?;
         if n == 0 {
             break;
         }
         hasher.update(&buf[..n]);
+        if let Some(p) = progress {
+            p.fetch_add(n as u64, Ordering::Relaxed);
+        }
     }
     Ok(hex::encode(hasher.finalize()))
 }
@@ -215,7 +473,13 @@ pub fn file_hash(path: &Path) -> std::io::Result<String> {
 pub fn extract_metadata_from_file(path: &Path, app: &tauri::AppHandle) -> ExtractedMetadata {
     let mut metadata = ExtractedMetadata::default();
     
-    let path_str = path.to_str().unwrap_or_default();
+    let path_str = match path_arg(path) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("[GUI] Skipping metadata extraction: {}", e);
+            return metadata;
+        }
+    };
     let args = vec!["-v", "quiet", "-print_format", "json", "-show_format", path_str];
     
     if let Ok(stdout) = run_ffprobe_sidecar(app, args) {
@@ -254,55 +518,518 @@ pub fn extract_metadata_from_file(path: &Path, app: &tauri::AppHandle) -> Extrac
     metadata
 }
 
+/// Probe codec, sample rate and channel count of the first audio stream,
+/// plus the container bitrate and the number of audio streams, using ffprobe (sidecar).
+/// A file without audio streams comes back with `audio_streams` at Some(0).
+pub fn probe_stream_info(path: &Path, app: &tauri::AppHandle) -> Option<StreamInfo> {
+    let path_str = path_arg(path).ok()?;
+    let args = vec![
+        "-v", "quiet",
+        "-print_format", "json",
+        "-select_streams", "a",
+        "-show_streams",
+        "-show_format",
+        path_str,
+    ];
+
+    let stdout = run_ffprobe_sidecar(app, args).ok()?;
+    let json = serde_json::from_slice::<serde_json::Value>(&stdout).ok()?;
+    let streams = json["streams"].as_array()?;
+    let Some(stream) = streams.first() else {
+        return Some(StreamInfo {
+            audio_streams: Some(0),
+            duration: json["format"]["duration"]
+                .as_str()
+                .and_then(|s| s.parse::<f64>().ok()),
+            ..Default::default()
+        });
+    };
+
+    Some(StreamInfo {
+        audio_streams: Some(streams.len() as u32),
+        codec: stream["codec_name"].as_str().map(|s| s.to_string()),
+        // ffprobe reports sample_rate as a string, channels as a number
+        sample_rate: stream["sample_rate"].as_str().and_then(|s| s.parse().ok()),
+        channels: stream["channels"].as_u64().map(|v| v as u32),
+        container_bitrate: json["format"]["bit_rate"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .map(|bps| (bps / 1000.0).round() as u32),
+        // ffprobe reports bits_per_raw_sample as a string, bits_per_sample as a
+        // number; 0 means the codec has no fixed depth
+        bit_depth: stream["bits_per_raw_sample"]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .or_else(|| stream["bits_per_sample"].as_u64().map(|v| v as u32))
+            .filter(|&bits| bits > 0),
+        duration: json["format"]["duration"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok()),
+    })
+}
+
+/// Why a file isn't worth sending to the analyzer: empty or under MIN_AUDIO_FILE_BYTES
+/// on disk, or a zero duration according to ffprobe. None when neither is known to be wrong.
+pub fn truncation_note(size: Option<u64>, duration: Option<f64>) -> Option<String> {
+    match (size, duration) {
+        (Some(0), _) => Some("Fichier vide (0 octet)".to_string()),
+        (Some(s), _) if s < MIN_AUDIO_FILE_BYTES => {
+            Some(format!("Fichier tronqué ({} octets)", s))
+        }
+        (_, Some(d)) if d <= 0.0 => {
+            Some("Durée nulle selon ffprobe : fichier tronqué ou corrompu".to_string())
+        }
+        _ => None,
+    }
+}
+
+fn truncated_analysis(note: String, stream: StreamInfo) -> Analysis {
+    Analysis {
+        warnings: vec![ScanWarning::new(ScanWarningCode::TruncatedFile, note.clone())],
+        note: Some(note),
+        status: ScanStatus::Error,
+        stream,
+        ..Default::default()
+    }
+}
+
+/// Peaks at or above this many dBFS count as clipping; volumedetect rounds to 0.1 dB
+const CLIPPING_PEAK_DB: f64 = -0.05;
+
+/// Read `max_volume` from the summary volumedetect prints on stderr
+fn parse_max_volume(stderr: &str) -> Option<f64> {
+    stderr.lines().rev().find_map(|line| {
+        let rest = line.split("max_volume:").nth(1)?;
+        rest.trim().trim_end_matches("dB").trim().parse::<f64>().ok()
+    })
+}
+
+/// Decode the first audio stream with ffmpeg's volumedetect and tell whether its
+/// peak reaches 0 dBFS
+pub fn detect_clipping(path: &Path, app: &tauri::AppHandle) -> Result<bool, String> {
+    let path_str = path_arg(path)?;
+    let args = vec![
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        path_str,
+        "-map",
+        "0:a:0",
+        "-af",
+        "volumedetect",
+        "-f",
+        "null",
+        "-",
+    ];
+    let stderr = run_ffmpeg_sidecar(app, args)?;
+    let peak = parse_max_volume(&String::from_utf8_lossy(&stderr))
+        .ok_or_else(|| "Mesure de crête illisible".to_string())?;
+    Ok(peak >= CLIPPING_PEAK_DB)
+}
+
+/// detect_clipping for a scanned file: it decodes the whole file, so it waits for an
+/// analyzer slot like the analyzer does, and is skipped once the scan is cancelled
+fn scan_clipping(path: &Path, app: &tauri::AppHandle, settings: &crate::settings::Settings) -> Option<bool> {
+    if SCAN_CANCELLED.load(Ordering::SeqCst) {
+        return None;
+    }
+    let _slot = ANALYZER_SLOTS.acquire(analyzer_slot_limit(settings.max_concurrent_analyses));
+    if SCAN_CANCELLED.load(Ordering::SeqCst) {
+        return None;
+    }
+    match detect_clipping(path, app) {
+        Ok(clipped) => Some(clipped),
+        Err(e) => {
+            log::warn!("[scan] Clipping check failed for {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Relative difference above which analyzed and container bitrates are reported as disagreeing
+const BITRATE_DISAGREEMENT_RATIO: f64 = 0.25;
+
+/// Describe a sharp disagreement between the analyzed and container bitrates, if any
+pub fn bitrate_disagreement(analyzed: u32, container: u32) -> Option<String> {
+    if analyzed == 0 || container == 0 {
+        return None;
+    }
+    let diff = (analyzed as f64 - container as f64).abs() / container as f64;
+    if diff > BITRATE_DISAGREEMENT_RATIO {
+        Some(format!(
+            "Container bitrate {} kbps differs from analyzed {} kbps ({:.0}%)",
+            container,
+            analyzed,
+            diff * 100.0
+        ))
+    } else {
+        None
+    }
+}
+
 /// Probe duration of an audio file using ffprobe (sidecar)
 pub fn probe_duration(path: &Path, app: &tauri::AppHandle) -> Option<f64> {
-    log::error!("[probe_duration] Probing: {:?}", path);
-    let path_str = path.to_string_lossy();
+    log::debug!("[probe_duration] Probing: {:?}", path);
+    let path_str = match path_arg(path) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("[probe_duration] {}", e);
+            return None;
+        }
+    };
     let args = vec![
         "-v", "error",
         "-show_entries", "format=duration",
         "-of", "default=noprint_wrappers=1:nokey=1",
-        &path_str,
+        path_str,
     ];
     
     match run_ffprobe_sidecar(app, args) {
         Ok(stdout) => {
             let text = String::from_utf8_lossy(&stdout);
-            log::error!("[probe_duration] Raw output: '{}'", text.trim());
+            log::trace!("[probe_duration] Raw output: '{}'", text.trim());
             let line = text.lines().next()?.trim();
             let duration = f64::from_str(line).ok();
-            log::error!("[probe_duration] Parsed duration: {:?}", duration);
+            log::debug!("[probe_duration] Parsed duration: {:?}", duration);
             duration
         }
         Err(e) => {
-            log::error!("[probe_duration] ffprobe failed: {}", e);
+            log::warn!("[probe_duration] ffprobe failed: {}", e);
             None
         }
     }
 }
 
-// New helper function to invoke whatsmybitrate sidecar
+/// Counting semaphore bounding how many analyzer processes run at once. Scan threads
+/// block in `acquire` until a slot frees up, so the rayon pool size doesn't decide
+/// how many librosa processes share the RAM.
+struct AnalyzerSlots {
+    busy: Mutex<usize>,
+    freed: Condvar,
+}
+
+struct AnalyzerSlot<'a>(&'a AnalyzerSlots);
+
+impl AnalyzerSlots {
+    const fn new() -> Self {
+        AnalyzerSlots {
+            busy: Mutex::new(0),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.busy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn busy(&self) -> usize {
+        *self.lock()
+    }
+
+    /// Wait for one of `limit` slots (at least 1)
+    fn acquire(&self, limit: usize) -> AnalyzerSlot<'_> {
+        let mut busy = self.lock();
+        while *busy >= limit.max(1) {
+            busy = self.freed.wait(busy).unwrap_or_else(|e| e.into_inner());
+        }
+        *busy += 1;
+        AnalyzerSlot(self)
+    }
+}
+
+impl Drop for AnalyzerSlot<'_> {
+    fn drop(&mut self) {
+        *self.0.lock() -= 1;
+        // Waiters may hold different limits if the setting changed mid-scan
+        self.0.freed.notify_all();
+    }
+}
+
+static ANALYZER_SLOTS: AnalyzerSlots = AnalyzerSlots::new();
+
+/// Analyzer processes running right now, across all scans and commands
+pub fn active_analyzers() -> usize {
+    ANALYZER_SLOTS.busy()
+}
+
+/// Concurrent analyzer processes for the `max_concurrent_analyses` setting
+pub fn analyzer_slot_limit(setting: usize) -> usize {
+    if setting > 0 {
+        setting
+    } else {
+        (num_cpus::get() / 2).max(1)
+    }
+}
+
+/// Tracks the session's first analyzer call, which can take many seconds while python
+/// imports librosa (or the bundled binary unpacks itself)
+#[derive(Default)]
+struct Warmup {
+    /// Set once an analyzer call has come back
+    warm: AtomicBool,
+    announced: AtomicBool,
+}
+
+impl Warmup {
+    /// True for the one call that should announce the warm-up
+    fn start(&self) -> bool {
+        !self.warm.load(Ordering::SeqCst) && !self.announced.swap(true, Ordering::SeqCst)
+    }
+
+    /// True for the first call to come back after the warm-up was announced
+    fn finish(&self) -> bool {
+        !self.warm.swap(true, Ordering::SeqCst) && self.announced.load(Ordering::SeqCst)
+    }
+}
+
+static WARMUP: Warmup = Warmup {
+    warm: AtomicBool::new(false),
+    announced: AtomicBool::new(false),
+};
+
+/// Run whatsmybitrate once on `file_path` and return its JSON answer. Accepted modes:
+/// - `"probe"`: `{"bitrate": ...}` only
+/// - `"analyze"`: the full analysis (bitrate, lossless flag, cutoff...)
+/// - `"spectrogram"` (alias `"spectrum"`): the full analysis plus a PNG rendered to
+///   `output`, which is required, reported back as `spectrogram_path`
+///
+/// `window` limits how many seconds of audio are loaded, starting the
+/// analysis_offset_seconds setting in (None loads the whole file from the start; an
+/// offset past the end of the track also reads from the start). `spectrogram`
+/// overrides the rendering of spectrogram modes and should be checked with
+/// `validate_spectrogram_options`. The analyzer, retries and concurrency come from
+/// `settings`.
 pub async fn invoke_whatsmybitrate(
     app: &tauri::AppHandle,
+    settings: &crate::settings::Settings,
     mode: &str, 
     file_path: &str,
     window: Option<u32>,
     output: Option<&str>,
-) -> Result<serde_json::Value, String> {
-    
-    let args = {
-        let mut a = vec![mode.to_string(), file_path.to_string()];
-        if let Some(w) = window {
-            a.push("--window".to_string());
-            a.push(w.to_string());
+    spectrogram: Option<&SpectrogramOptions>,
+) -> Result<serde_json::Value, AnalyzerError> {
+    if file_path.is_empty() {
+        return Err("Refusing to invoke whatsmybitrate with an empty path".to_string().into());
+    }
+    if matches!(mode, "spectrogram" | "spectrum") && output.is_none_or(str::is_empty) {
+        return Err(format!("whatsmybitrate {} mode needs an output PNG path", mode).into());
+    }
+
+    let offset = window.map(|_| settings.analysis_offset_seconds);
+    let args = analyzer_args(mode, file_path, window, offset, output, spectrogram);
+    let analyzer = resolve_analyzer(app, &settings.analyzer_mode)?;
+    let retries = settings.analyzer_retries;
+    let slots = analyzer_slot_limit(settings.max_concurrent_analyses);
+    let label = analyzer.label;
+
+    // "analyzer_warming" brackets the first call of the session, whatever its outcome
+    if WARMUP.start() {
+        let _ = app.emit("analyzer_warming", AnalyzerWarming { warming: true, analyzer: label });
+    }
+    let result =
+        tauri::async_runtime::spawn_blocking(move || {
+            let _slot = ANALYZER_SLOTS.acquire(slots);
+            run_analyzer_with_retry(&analyzer, &args, retries)
+        })
+        .await
+        .map_err(|e| e.to_string());
+    if WARMUP.finish() {
+        let _ = app.emit("analyzer_warming", AnalyzerWarming { warming: false, analyzer: label });
+    }
+    Ok(result??)
+}
+
+/// The analyzer's arguments after any leading script path
+fn analyzer_args(
+    mode: &str,
+    file_path: &str,
+    window: Option<u32>,
+    offset: Option<u32>,
+    output: Option<&str>,
+    spectrogram: Option<&SpectrogramOptions>,
+) -> Vec<String> {
+    let mut a = vec![mode.to_string(), file_path.to_string()];
+    if let Some(w) = window {
+        a.push("--window".to_string());
+        a.push(w.to_string());
+    }
+    // Left out when zero so analyzer builds without --offset keep working
+    if let Some(o) = offset.filter(|o| *o > 0) {
+        a.push("--offset".to_string());
+        a.push(o.to_string());
+    }
+    if let Some(o) = output {
+        a.push("--output".to_string());
+        a.push(o.to_string());
+    }
+    if let Some(opts) = spectrogram {
+        a.extend(spectrogram_args(opts));
+    }
+    a
+}
+
+/// Run the analyzer once on `path` in a one-off process, the way a scan would (same
+/// resolution, window and offset, no worker, no retry), and keep its raw output
+pub fn diagnose_analysis(app: &tauri::AppHandle, path: &Path) -> Result<AnalyzerDiagnostics, String> {
+    let settings = crate::settings::load_settings(app);
+    let (_, window) = settings.thresholds_for(path, None, None);
+    // Only "fraction" mode needs the duration; nothing else here runs ffprobe
+    let duration = (settings.analysis_window_mode == "fraction")
+        .then(|| probe_duration(path, app))
+        .flatten();
+    let window = settings.window_for_duration(window, duration);
+    let args = analyzer_args(
+        "analyze",
+        path_arg(path)?,
+        Some(window),
+        Some(settings.analysis_offset_seconds),
+        None,
+        None,
+    );
+    let analyzer = resolve_analyzer(app, &settings.analyzer_mode)?;
+    let argv = std::iter::once(analyzer.program.to_string_lossy().to_string())
+        .chain(analyzer.leading_args.iter().map(|a| a.to_string_lossy().to_string()))
+        .chain(args.iter().cloned())
+        .collect();
+
+    let started = std::time::Instant::now();
+    let output = crate::processes::tracked_output(&mut analyzer_command(&analyzer, &args));
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    let mut report = AnalyzerDiagnostics {
+        analyzer: analyzer.label,
+        program: analyzer.program.to_string_lossy().to_string(),
+        argv,
+        elapsed_ms,
+        ..Default::default()
+    };
+    match output {
+        Ok(output) => {
+            report.exit_code = output.status.code();
+            report.abnormal_exit = abnormal_exit(&output.status);
+            report.stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            report.stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            report.parsed = serde_json::from_slice(&output.stdout).ok();
+        }
+        Err(e) => report.spawn_error = Some(e.to_string()),
+    }
+    Ok(report)
+}
+
+pub const SPECTROGRAM_WIDTH_RANGE: std::ops::RangeInclusive<u32> = 400..=4000;
+pub const SPECTROGRAM_HEIGHT_RANGE: std::ops::RangeInclusive<u32> = 200..=3000;
+pub const SPECTROGRAM_MAX_FREQ_KHZ_RANGE: std::ops::RangeInclusive<f64> = 1.0..=96.0;
+
+/// Reject spectrogram sizes and frequency caps the analyzer can't sensibly render
+pub fn validate_spectrogram_options(opts: &SpectrogramOptions) -> Result<(), String> {
+    if let Some(w) = opts.width.filter(|w| !SPECTROGRAM_WIDTH_RANGE.contains(w)) {
+        return Err(format!(
+            "Largeur invalide: {} (entre {} et {} px)",
+            w, SPECTROGRAM_WIDTH_RANGE.start(), SPECTROGRAM_WIDTH_RANGE.end()
+        ));
+    }
+    if let Some(h) = opts.height.filter(|h| !SPECTROGRAM_HEIGHT_RANGE.contains(h)) {
+        return Err(format!(
+            "Hauteur invalide: {} (entre {} et {} px)",
+            h, SPECTROGRAM_HEIGHT_RANGE.start(), SPECTROGRAM_HEIGHT_RANGE.end()
+        ));
+    }
+    if let Some(f) = opts.max_freq_khz.filter(|f| !SPECTROGRAM_MAX_FREQ_KHZ_RANGE.contains(f)) {
+        return Err(format!(
+            "Fréquence max invalide: {} kHz (entre {} et {} kHz)",
+            f, SPECTROGRAM_MAX_FREQ_KHZ_RANGE.start(), SPECTROGRAM_MAX_FREQ_KHZ_RANGE.end()
+        ));
+    }
+    Ok(())
+}
+
+/// CLI flags for the options that are set; unset ones keep the analyzer defaults
+fn spectrogram_args(opts: &SpectrogramOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(w) = opts.width {
+        args.extend(["--width".to_string(), w.to_string()]);
+    }
+    if let Some(h) = opts.height {
+        args.extend(["--height".to_string(), h.to_string()]);
+    }
+    if let Some(f) = opts.max_freq_khz {
+        args.extend(["--max-freq".to_string(), f.to_string()]);
+    }
+    args
+}
+
+/// A resolved whatsmybitrate invocation: the bundled binary, or python3 + the dev script
+struct AnalyzerCommand {
+    program: PathBuf,
+    /// Arguments placed before the mode (the script path for python)
+    leading_args: Vec<PathBuf>,
+    envs: HashMap<String, String>,
+    /// "binary" or "python", used in logs and error messages
+    label: &'static str,
+}
+
+/// Why invoke_whatsmybitrate produced no output
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnalyzerError {
+    /// No analyzer could be found under the analyzer_mode setting
+    Missing(String),
+    /// The python analyzer can't import its requirements
+    DepsMissing(String),
+    /// The analyzer couldn't be run or failed on the file
+    Failed(String),
+}
+
+impl std::fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalyzerError::Missing(message)
+            | AnalyzerError::DepsMissing(message)
+            | AnalyzerError::Failed(message) => f.write_str(message),
         }
-        if let Some(o) = output {
-            a.push("--output".to_string());
-            a.push(o.to_string());
+    }
+}
+
+/// A failed run. Python reports missing requirements only as an ImportError on
+/// stderr, so this is the one place they are told apart from other failures.
+impl From<String> for AnalyzerError {
+    fn from(message: String) -> Self {
+        if python_deps_missing(&message) {
+            AnalyzerError::DepsMissing(message)
+        } else {
+            AnalyzerError::Failed(message)
         }
-        a
+    }
+}
+
+impl From<AnalyzerError> for String {
+    fn from(e: AnalyzerError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Locate whatsmybitrate as `mode` (the `analyzer_mode` setting) asks: the bundled
+/// onedir binary, the python script for development, or in "auto" the binary then
+/// the script
+fn resolve_analyzer(app: &tauri::AppHandle, mode: &str) -> Result<AnalyzerCommand, AnalyzerError> {
+    pick_analyzer(mode, || bundled_analyzer(app), || python_analyzer(app))
+}
+
+fn pick_analyzer<T>(
+    mode: &str,
+    bundled: impl FnOnce() -> Option<T>,
+    python: impl FnOnce() -> Option<T>,
+) -> Result<T, AnalyzerError> {
+    let found = match mode {
+        "binary" => bundled().ok_or("Bundled whatsmybitrate not found (analyzer_mode=binary)"),
+        "python" => python().ok_or("whatsmybitrate dev script missing (analyzer_mode=python)"),
+        _ => bundled()
+            .or_else(python)
+            .ok_or("Bundled whatsmybitrate not found and dev script missing"),
     };
-    
+    found.map_err(|message| AnalyzerError::Missing(message.to_string()))
+}
+
+/// The bundled onedir whatsmybitrate, if this build ships one
+fn bundled_analyzer(app: &tauri::AppHandle) -> Option<AnalyzerCommand> {
     // Determine binary name based on platform
     #[cfg(windows)]
     let bin_name = "whatsmybitrate.exe";
@@ -325,129 +1052,228 @@ pub async fn invoke_whatsmybitrate(
         "whatsmybitrate".to_string(),
     ];
 
-    let mut exe_path = None;
-    
     // Try to find the bundled onedir executable in resources
     for name in resource_names {
-        if let Some(path) = get_resource_path(app, &name) {
+        if let Some(path) = resolve_resource(app, &name) {
             let candidate = if path.is_file() {
                 path
             } else {
                 path.join(bin_name)
             };
             
-            log::info!("[whatsmybitrate] Checking for binary at: {:?}", candidate);
+            log::debug!("[whatsmybitrate] Checking for binary at: {:?}", candidate);
             if candidate.exists() {
-                exe_path = Some(candidate);
-                break;
+                // Explicitly add FFPROBE_PATH to envs if we can find the resource
+                let mut envs = get_env_with_resources(app);
+                #[cfg(target_os = "windows")]
+                let ffprobe_name = "ffprobe.exe";
+                #[cfg(not(target_os = "windows"))]
+                let ffprobe_name = "ffprobe";
+
+                // Contents/MacOS/ of a mac bundle is among the resource candidates
+                if let Some(ffprobe_path) = resolve_resource(app, ffprobe_name) {
+                    envs.insert("FFPROBE_PATH".to_string(), ffprobe_path.to_string_lossy().to_string());
+                    log::debug!("[whatsmybitrate] Injected FFPROBE_PATH: {:?}", ffprobe_path);
+                } else {
+                    log::warn!("[whatsmybitrate] Could not resolve ffprobe path for injection");
+                }
+                // ffmpeg decodes video containers; it isn't bundled, so without a
+                // sidecar the analyzer falls back to the one on PATH
+                #[cfg(target_os = "windows")]
+                let ffmpeg_name = "ffmpeg.exe";
+                #[cfg(not(target_os = "windows"))]
+                let ffmpeg_name = "ffmpeg";
+                if let Some(ffmpeg_path) = resolve_resource(app, ffmpeg_name) {
+                    envs.insert("FFMPEG_PATH".to_string(), ffmpeg_path.to_string_lossy().to_string());
+                    log::debug!("[whatsmybitrate] Injected FFMPEG_PATH: {:?}", ffmpeg_path);
+                }
+
+                return Some(AnalyzerCommand {
+                    program: candidate,
+                    leading_args: Vec::new(),
+                    envs,
+                    label: "binary",
+                });
             }
         }
     }
+    None
+}
 
-    // Fallback to python3 for development if bundled binary not found
-    if exe_path.is_none() {
-        let exe_dir = std::env::current_exe().map_err(|e| e.to_string())?.parent().ok_or("no parent")?.to_path_buf();
-        let vendor_dir = exe_dir.join("../vendor/whatsmybitrate");
-        let script_path = vendor_dir.join("whatsmybitrate_cli.py");
-         
-        if script_path.exists() {
-             let envs = get_env_with_resources(app);
-             let script_path_clone = script_path.clone();
-
-             // Run blocking python command
-             return tauri::async_runtime::spawn_blocking(move || {
-                 let python = "python3";
-                 let mut cmd = Command::new(python);
-                 
-                 #[cfg(windows)]
-                 {
-                     use std::os::windows::process::CommandExt;
-                     const CREATE_NO_WINDOW: u32 = 0x08000000;
-                     cmd.creation_flags(CREATE_NO_WINDOW);
-                 }
-
-                 cmd.arg(&script_path_clone);
-                 for arg in args {
-                     cmd.arg(arg);
-                 }
-                 
-                 let output = cmd
-                    .envs(&envs)
-                    .output()
-                    .map_err(|e| format!("python3 failed: {}", e))?;
-
-                 if !output.status.success() {
-                    return Err(String::from_utf8_lossy(&output.stderr).to_string());
-                 }
-
-                 let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
-                 let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
-                 serde_json::from_slice(&output.stdout)
-                    .map_err(|e| format!("Failed to parse output (python): {}. Raw stdout: '{}'. Stderr: '{}'", e, stdout_str, stderr_str))
-            }).await.map_err(|e| e.to_string())?
-        }
-    }
-
-    let exe_final = exe_path.ok_or("Bundled whatsmybitrate not found and dev script missing")?;
-    let exe_clone = exe_final.clone();
-    
-    // Explicitly add FFPROBE_PATH to envs if we can find the resource
-    let mut envs = get_env_with_resources(app);
-    #[cfg(target_os = "windows")]
-    let ffprobe_name = "ffprobe.exe";
-    #[cfg(not(target_os = "windows"))]
-    let ffprobe_name = "ffprobe";
+/// python3 running the vendored whatsmybitrate script, for development
+fn python_analyzer(app: &tauri::AppHandle) -> Option<AnalyzerCommand> {
+    let script_path = resolve_vendor_file(app, "whatsmybitrate_cli.py")?;
+    Some(AnalyzerCommand {
+        program: PathBuf::from("python3"),
+        leading_args: vec![script_path],
+        envs: get_env_with_resources(app),
+        label: "python",
+    })
+}
 
-    // Use the robust sidecar resolution to find ffprobe (handles Contents/MacOS/ on bundle)
-    if let Some(ffprobe_path) = resolve_sidecar_path(app, ffprobe_name) {
-        envs.insert("FFPROBE_PATH".to_string(), ffprobe_path.to_string_lossy().to_string());
-        log::info!("[whatsmybitrate] Injected FFPROBE_PATH: {:?}", ffprobe_path);
-    } else {
-        log::info!("[whatsmybitrate] WARNING: Could not resolve ffprobe path for injection");
+/// Ok if a whatsmybitrate binary or dev script can be found, else why not
+pub fn analyzer_available(app: &tauri::AppHandle, mode: &str) -> Result<(), String> {
+    resolve_analyzer(app, mode).map(|_| ()).map_err(String::from)
+}
+
+/// requirements.txt of the python whatsmybitrate, from the dev checkout or the
+/// bundled resources
+pub fn find_requirements(app: &tauri::AppHandle) -> Option<PathBuf> {
+    resolve_vendor_file(app, "requirements.txt")
+}
+
+/// Delay before the first retry; doubled on each further attempt
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Run the analyzer, retrying transient failures up to `retries` extra times with backoff
+fn run_analyzer_with_retry(
+    analyzer: &AnalyzerCommand,
+    args: &[String],
+    retries: u32,
+) -> Result<serde_json::Value, String> {
+    let mut attempt = 0;
+    loop {
+        match run_once(analyzer, args) {
+            Ok(json) => return Ok(json),
+            Err(e) if attempt < retries && !is_deterministic_failure(&e) => {
+                let delay = RETRY_BASE_DELAY_MS * 2u64.pow(attempt);
+                attempt += 1;
+                log::warn!(
+                    "[whatsmybitrate] Attempt {}/{} failed ({}), retrying in {} ms",
+                    attempt,
+                    retries + 1,
+                    e.trim(),
+                    delay
+                );
+                std::thread::sleep(std::time::Duration::from_millis(delay));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Failures that will not go away by retrying (missing file, bad arguments, missing analyzer)
+fn is_deterministic_failure(error: &str) -> bool {
+    const MARKERS: [&str; 5] = [
+        "File not found",
+        "No such file",
+        "Unsupported path encoding",
+        "required for",
+        "execution failed",
+    ];
+    MARKERS.iter().any(|m| error.contains(m))
+}
+
+/// Run one attempt, on a persistent worker for the python fallback when possible
+fn run_once(analyzer: &AnalyzerCommand, args: &[String]) -> Result<serde_json::Value, String> {
+    if analyzer.label == "python" {
+        if let Some(script) = analyzer.leading_args.first() {
+            match analyzer_worker::request(&analyzer.program, script, &analyzer.envs, args) {
+                Ok(outcome) => return outcome,
+                Err(e) => log::warn!(
+                    "[whatsmybitrate] Analyzer worker unavailable ({}), spawning a one-off process",
+                    e
+                ),
+            }
+        }
     }
+    run_analyzer(analyzer, args)
+}
 
-    // Run bundled executable
-    tauri::async_runtime::spawn_blocking(move || {
-         let mut cmd = Command::new(&exe_clone);
-         cmd.envs(&envs);
+/// The process for one analyzer run, without a console window on Windows
+fn analyzer_command(analyzer: &AnalyzerCommand, args: &[String]) -> Command {
+    let mut cmd = Command::new(&analyzer.program);
+    cmd.envs(&analyzer.envs);
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
 
-         #[cfg(target_os = "windows")]
-         {
-             let _ = cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-         }
+    cmd.args(&analyzer.leading_args);
+    cmd.args(args);
+    cmd
+}
 
-         for arg in args {
-             cmd.arg(arg);
-         }
-         
-         // On macOS/Linux, we might need to preserve environment or set minimal
-         // but onedir should be self-contained. 
-         // However, on macOS, adhoc signing might require clean env?
-         // Let's inherit env for now.
+/// Run one analyzer process and parse its JSON output
+fn run_analyzer(analyzer: &AnalyzerCommand, args: &[String]) -> Result<serde_json::Value, String> {
+    let output = crate::processes::tracked_output(&mut analyzer_command(analyzer, args))
+        .map_err(|e| format!("whatsmybitrate ({}) execution failed: {}", analyzer.label, e))?;
 
-         let output = cmd
-            .output()
-            .map_err(|e| format!("whatsmybitrate execution failed: {}", e))?;
+    if !output.status.success() {
+        // The CLI reports handled errors as {"error": ...} on stdout before exiting non-zero
+        let reported = serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .ok()
+            .and_then(|json| json.get("error").and_then(|e| e.as_str()).map(|e| e.to_string()));
+        if let Some(reported) = reported {
+            return Err(reported);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(match abnormal_exit(&output.status) {
+            Some(crash) if stderr.is_empty() => {
+                format!("whatsmybitrate ({}) crashed: {}", analyzer.label, crash)
+            }
+            Some(crash) => format!("whatsmybitrate ({}) crashed: {}. Stderr: {}", analyzer.label, crash, stderr),
+            None if stderr.is_empty() => format!(
+                "whatsmybitrate ({}) failed with {} and no error output",
+                analyzer.label, output.status
+            ),
+            None => stderr,
+        });
+    }
 
-         if !output.status.success() {
-            return Err(String::from_utf8_lossy(&output.stderr).to_string());
-         }
+    let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse output ({}): {}. Raw stdout: '{}'. Stderr: '{}'", analyzer.label, e, stdout_str, stderr_str))
+}
 
-         let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
-         let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
-         serde_json::from_slice(&output.stdout)
-            .map_err(|e| format!("Failed to parse output (binary): {}. Raw stdout: '{}'. Stderr: '{}'", e, stdout_str, stderr_str))
-    }).await.map_err(|e| e.to_string())?
+/// Describe an exit that wasn't the program's own decision: killed by a signal on Unix,
+/// or an NTSTATUS crash code (access violation, stack overflow...) on Windows
+fn abnormal_exit(status: &std::process::ExitStatus) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            let name = match signal {
+                4 => " (SIGILL)",
+                6 => " (SIGABRT)",
+                8 => " (SIGFPE)",
+                9 => " (SIGKILL, possibly out of memory)",
+                11 => " (SIGSEGV)",
+                15 => " (SIGTERM)",
+                _ => "",
+            };
+            let core = if status.core_dumped() { ", core dumped" } else { "" };
+            return Some(format!("killed by signal {}{}{}", signal, name, core));
+        }
+    }
+    #[cfg(windows)]
+    {
+        if let Some(code) = status.code().map(|c| c as u32).filter(|c| *c >= 0xC000_0000) {
+            let name = match code {
+                0xC000_0005 => " (access violation)",
+                0xC000_00FD => " (stack overflow)",
+                0xC000_0017 => " (out of memory)",
+                0xC000_013A => " (interrupted)",
+                _ => "",
+            };
+            return Some(format!("exit code 0x{:08X}{}", code, name));
+        }
+    }
+    None
 }
 
 /// Probe bitrate using whatsmybitrate
-pub fn probe_bitrate(path: &Path, app: &tauri::AppHandle) -> Option<u32> {
+pub fn probe_bitrate(path: &Path, app: &tauri::AppHandle, settings: &crate::settings::Settings) -> Option<u32> {
     let result = tauri::async_runtime::block_on(invoke_whatsmybitrate(
         app, 
+        settings,
         "probe", 
-        path.to_str()?, 
-        None, 
-        None
+        path_arg(path).ok()?, 
+        None,
+        None,
+        None,
     )).ok()?;
     
     result.get("bitrate")
@@ -455,138 +1281,954 @@ pub fn probe_bitrate(path: &Path, app: &tauri::AppHandle) -> Option<u32> {
         .map(|v| v.round() as u32)
 }
 
-/// Analyze a single file with whatsmybitrate
+/// Outcome of analyzing a single file with whatsmybitrate
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    pub bitrate: Option<u32>,
+    pub is_lossless: Option<bool>,
+    /// Highest frequency with significant energy (spectral cutoff), in Hz
+    pub cutoff_hz: Option<u32>,
+    pub note: Option<String>,
+    pub status: ScanStatus,
+    /// ffprobe stream properties, including the container bitrate
+    pub stream: StreamInfo,
+    pub warnings: Vec<ScanWarning>,
+    /// Answered from the cache instead of the analyzer
+    pub cached: bool,
+    /// The cache was looked up and had no usable entry for the file
+    pub cache_missed: bool,
+    /// Content hash, computed only when the cache is on
+    pub hash: Option<String>,
+    /// Whether the peak reaches 0 dBFS; None unless `detect_clipping` is on
+    pub clipping: Option<bool>,
+}
+
+/// Whether the python analyzer can't import its requirements
+fn python_deps_missing(error: &str) -> bool {
+    error.contains("No module named") || error.contains("ModuleNotFoundError")
+}
+
+/// Failures that hit every file alike because no working analyzer is installed, as
+/// opposed to one file being unreadable
+pub fn analyzer_unavailable(warning: &ScanWarning) -> bool {
+    matches!(
+        warning.code,
+        ScanWarningCode::AnalyzerMissing | ScanWarningCode::AnalyzerDepsMissing
+    )
+}
+
+/// Warning for an analysis that failed outright
+pub fn analysis_failure_warning(error: &AnalysisError) -> ScanWarning {
+    ScanWarning::new(
+        error.code.unwrap_or(ScanWarningCode::AnalyzerFailed),
+        error.message.clone(),
+    )
+}
+
+/// Warnings derived from the ffprobe pass: a failed probe, or a container bitrate
+/// that contradicts the analysis
+fn stream_warnings(probed: bool, bitrate: Option<u32>, is_lossless: Option<bool>, stream: &StreamInfo) -> Vec<ScanWarning> {
+    if !probed {
+        return vec![ScanWarning::new(
+            ScanWarningCode::ProbeUnavailable,
+            "ffprobe n'a pas pu lire le flux audio",
+        )];
+    }
+    if is_lossless == Some(true) {
+        return Vec::new();
+    }
+    match (bitrate, stream.container_bitrate) {
+        (Some(analyzed), Some(container)) => bitrate_disagreement(analyzed, container)
+            .map(|d| ScanWarning::new(ScanWarningCode::BitrateMismatch, d))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// ffprobe codec names of lossless formats; PCM variants are covered by their prefix
+pub fn is_lossless_codec(codec: &str) -> bool {
+    codec.starts_with("pcm_") || matches!(codec, "flac" | "alac" | "wavpack" | "ape" | "tta")
+}
+
+/// Container bitrate a lossy file needs, relative to the threshold, to pass the quick
+/// pre-filter without spectral analysis
+const PREFILTER_MARGIN: f64 = 1.25;
+
+/// quick_prefilter's call from the ffprobe figures alone: Some((is_lossless, note)) for
+/// files that are clearly fine (lossless codec, or a container bitrate well above
+/// `min`), None for files that still need the analyzer
+fn prefilter_verdict(stream: &StreamInfo, min: u32) -> Option<(bool, String)> {
+    if let Some(codec) = stream.codec.as_deref().filter(|c| is_lossless_codec(c)) {
+        return Some((
+            true,
+            format!("Pré-filtre : codec sans perte ({}), analyse spectrale non effectuée", codec),
+        ));
+    }
+    let container = stream.container_bitrate?;
+    (stream.codec.is_some() && container as f64 >= min as f64 * PREFILTER_MARGIN).then(|| {
+        (
+            false,
+            format!(
+                "Pré-filtre : débit du conteneur {} kbps, analyse spectrale non effectuée",
+                container
+            ),
+        )
+    })
+}
+
+/// Why analyze_with_wmb_single produced no analysis, with the ffprobe figures gathered
+/// before it failed so the error row can still show them
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisError {
+    pub message: String,
+    pub stream: StreamInfo,
+    /// What kind of failure this is; None for a plain analyzer failure
+    pub code: Option<ScanWarningCode>,
+    /// The cache was looked up and had no usable entry for the file
+    pub cache_missed: bool,
+}
+
+impl From<String> for AnalysisError {
+    fn from(message: String) -> Self {
+        AnalysisError {
+            message,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<AnalysisError> for String {
+    fn from(e: AnalysisError) -> Self {
+        e.message
+    }
+}
+
+/// ffprobe figures of `path`, and whether the probe found an audio stream
+fn probe_stream(path: &Path, app: &tauri::AppHandle) -> (bool, StreamInfo) {
+    match probe_stream_info(path, app) {
+        Some(stream) => (stream.audio_streams.is_some_and(|n| n > 0), stream),
+        None => (false, StreamInfo::default()),
+    }
+}
+
+/// Row for a video container in which ffprobe found no audio stream at all
+fn no_audio_analysis(stream: StreamInfo) -> Analysis {
+    Analysis {
+        note: Some("Aucune piste audio dans ce fichier vidéo".to_string()),
+        status: ScanStatus::Skipped,
+        stream,
+        ..Default::default()
+    }
+}
+
+/// Analyze a single file with whatsmybitrate. `min` and `analysis_window` are the
+/// file's thresholds; everything else comes from `settings`.
 pub fn analyze_with_wmb_single(
     path: &Path,
     app: &tauri::AppHandle, // Added app handle
+    settings: &crate::settings::Settings,
     min: u32,
     analysis_window: u32,
     cache_enabled: bool,
     cache: &Arc<Mutex<HashMap<String, CacheEntry>>>,
-) -> Result<(Option<u32>, Option<bool>, Option<String>, String), String> {
-    let hash = if cache_enabled {
-        file_hash(path).ok()
+) -> Result<Analysis, AnalysisError> {
+    let path_str = path_arg(path)?;
+    // Empty and truncated files would only get a cryptic analyzer error
+    let size = fs::metadata(path).ok().map(|m| m.len());
+    if let Some(note) = truncation_note(size, None) {
+        log::warn!("[scan] Not analyzing {:?}: {}", path, note);
+        return Ok(truncated_analysis(note, StreamInfo::default()));
+    }
+    let analyzer_version = tool_versions(app).whatsmybitrate;
+    let mut warnings = Vec::new();
+
+    // ffprobe runs before the cache lookup only for the pre-filter; cache hits
+    // otherwise reuse the figures stored with their entry
+    let mut probed = None;
+    if settings.quick_prefilter {
+        let (stream_probed, stream) = probe_stream(path, app);
+        if settings.is_video(path) && stream.audio_streams == Some(0) {
+            return Ok(no_audio_analysis(stream));
+        }
+        if let Some(note) = truncation_note(None, stream.duration) {
+            log::warn!("[scan] Not analyzing {:?}: {}", path, note);
+            return Ok(truncated_analysis(note, stream));
+        }
+        if let Some((is_lossless, note)) = prefilter_verdict(&stream, min) {
+            return Ok(Analysis {
+                bitrate: if is_lossless { None } else { stream.container_bitrate },
+                is_lossless: Some(is_lossless),
+                note: Some(note),
+                status: ScanStatus::Ok,
+                warnings: stream_warnings(stream_probed, None, Some(is_lossless), &stream),
+                stream,
+                clipping: settings
+                    .detect_clipping
+                    .then(|| scan_clipping(path, app, settings))
+                    .flatten(),
+                ..Default::default()
+            });
+        }
+        probed = Some((stream_probed, stream));
+    }
+
+    let mut hash = if cache_enabled {
+        // Reported by scan_state for the files a scan is working on
+        let hashed = app
+            .try_state::<crate::scan_state::ScanTracker>()
+            .and_then(|t| t.hash_progress());
+        match file_hash_with_progress(path, hashed.as_deref(), Some(&SCAN_CANCELLED)) {
+            Ok(h) => Some(h),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                return Err(AnalysisError {
+                    message: "Analyse annulée".to_string(),
+                    stream: probed.map(|(_, stream)| stream).unwrap_or_default(),
+                    code: Some(ScanWarningCode::Cancelled),
+                    // Cancelled while hashing, before any cache lookup
+                    cache_missed: false,
+                });
+            }
+            Err(e) => {
+                warnings.push(ScanWarning::new(
+                    ScanWarningCode::HashFailed,
+                    format!("Impossible de calculer l'empreinte du fichier: {}", e),
+                ));
+                None
+            }
+        }
     } else {
         None
     };
     
     if cache_enabled {
-        if let Some(h) = &hash {
-            if let Ok(guard) = cache.lock() {
-                if let Some(entry) = guard.get(h) {
-                    // Check if entry is valid (has bitrate OR is lossless)
-                    let is_valid_entry = entry.bitrate.is_some() || entry.is_lossless.unwrap_or(false);
-                    
-                    if is_valid_entry {
-                        let status = match (entry.bitrate, entry.is_lossless) {
-                            (Some(b), _) if b < min => "bad".to_string(),
-                            (Some(_), _) => "ok".to_string(), 
-                            (None, Some(true)) => "ok".to_string(), // Lossless
-                            _ => "ok".to_string(), // Should be covered by is_valid_entry
-                        };
-                        return Ok((entry.bitrate, entry.is_lossless, entry.note.clone(), status));
-                    } else {
-                        // Entry exists but is incomplete (failed analysis) - ignore it and re-scan
-                        // log::info!("[scan] Ignoring incomplete cache entry for {:?}", path);
+        let accept_unversioned = settings.unversioned_cache == "accept";
+        // Entries that are incomplete (failed analysis), from another analyzer version
+        // or over another stretch of the file are ignored and the file re-scanned
+        let usable = |entry: &CacheEntry| {
+            let duration = entry.stream.as_ref().and_then(|s| s.duration);
+            (entry.bitrate.is_some() || entry.is_lossless.unwrap_or(false))
+                && cache_entry_current(entry, analyzer_version.as_deref(), accept_unversioned)
+                && cache_entry_window_matches(
+                    entry,
+                    settings.analysis_offset_seconds,
+                    settings.window_for_duration(analysis_window, duration),
+                    accept_unversioned,
+                )
+        };
+        let cached = hash
+            .as_ref()
+            .and_then(|h| lock_cache(cache).get(h).filter(|e| usable(e)).cloned())
+            .or_else(|| {
+                // Last resort: the analysis embedded in the file by embed_analysis, which
+                // survives cache loss and moves between machines with the file
+                let mut entry = crate::tagging::read_analysis_tag(path)?;
+                // The tag doesn't carry the duration a "fraction" window depends on
+                let (stream_probed, stream) =
+                    probed.clone().unwrap_or_else(|| probe_stream(path, app));
+                if stream_probed {
+                    entry.stream = Some(stream);
+                }
+                let entry = Some(entry).filter(|e| usable(e))?;
+                log::debug!("[scan] Using embedded analysis tag for {:?}", path);
+                if let Some(h) = &hash {
+                    let mut guard = lock_cache(cache);
+                    guard.insert(h.clone(), entry.clone());
+                    enforce_cache_limit(&mut guard, settings.cache_max_entries);
+                }
+                Some(entry)
+            });
+
+        if let Some(entry) = cached {
+            let (stream_probed, stream) = match (probed, entry.stream.clone()) {
+                (Some(probed), _) => probed,
+                (None, Some(stream)) => (true, stream),
+                // Entries from before stream figures were cached, or whose probe
+                // failed: probe now and keep the figures for next time
+                (None, None) => probe_stream(path, app),
+            };
+            // Decoded once per cached file, then kept with the entry
+            let clipping = if settings.detect_clipping {
+                entry.clipping.or_else(|| scan_clipping(path, app, settings))
+            } else {
+                None
+            };
+            if let Some(h) = &hash {
+                if let Some(e) = lock_cache(cache).get_mut(h) {
+                    // Keep the recorded path current so prune_cache follows moved files
+                    e.path = Some(path.to_string_lossy().to_string());
+                    if stream_probed && e.stream.is_none() {
+                        e.stream = Some(stream.clone());
+                    }
+                    if e.clipping.is_none() {
+                        e.clipping = clipping;
                     }
                 }
             }
+
+            let status = match entry.bitrate {
+                Some(b) if b < min => ScanStatus::Bad,
+                _ => ScanStatus::Ok, // usable entries have a bitrate or are lossless
+            };
+            let note = with_container_check(entry.note.clone(), entry.bitrate, entry.is_lossless, &stream);
+            warnings.extend(stream_warnings(stream_probed, entry.bitrate, entry.is_lossless, &stream));
+            return Ok(Analysis {
+                bitrate: entry.bitrate,
+                is_lossless: entry.is_lossless,
+                cutoff_hz: entry.cutoff_hz,
+                note,
+                status,
+                stream,
+                warnings,
+                cached: true,
+                cache_missed: false,
+                hash,
+                clipping,
+            });
         }
     }
 
+    // Reaching here with a hash means the lookup above found nothing usable
+    let cache_missed = cache_enabled && hash.is_some();
+    let (stream_probed, stream) = match probed {
+        Some(probed) => probed,
+        None => probe_stream(path, app),
+    };
+    if settings.is_video(path) && stream.audio_streams == Some(0) {
+        return Ok(Analysis { cache_missed, ..no_audio_analysis(stream) });
+    }
+    if let Some(note) = truncation_note(None, stream.duration) {
+        log::warn!("[scan] Not analyzing {:?}: {}", path, note);
+        return Ok(Analysis { cache_missed, ..truncated_analysis(note, stream) });
+    }
 
+    let analysis_window = settings.window_for_duration(analysis_window, stream.duration);
     let parsed = tauri::async_runtime::block_on(invoke_whatsmybitrate(
         app,
+        settings,
         "analyze",
-        path.to_str().unwrap_or_default(),
+        path_str,
         Some(analysis_window),
-        None
-    ))?;
+        None,
+        None,
+    ))
+    .map_err(|e| AnalysisError {
+        code: match e {
+            AnalyzerError::Missing(_) => Some(ScanWarningCode::AnalyzerMissing),
+            AnalyzerError::DepsMissing(_) => Some(ScanWarningCode::AnalyzerDepsMissing),
+            AnalyzerError::Failed(_) => None,
+        },
+        message: e.to_string(),
+        stream: stream.clone(),
+        cache_missed,
+    })?;
 
     let est = parsed
         .get("estimated_bitrate_numeric")
         .and_then(|v| v.as_f64())
         .map(|v| v.round() as u32);
     let lossless = parsed.get("is_lossless").and_then(|v| v.as_bool());
+    let cutoff_hz = parsed
+        .get("max_frequency")
+        .and_then(|v| v.as_f64())
+        .map(|v| v.round() as u32);
     let err = parsed
         .get("error")
         .and_then(|v| v.as_str())
         .filter(|s| !s.is_empty())
         .map(|s| s.to_string());
 
-    let status = match (err.is_some(), est, lossless) {
-        (true, _, _) => {
+    let status = match (&err, est, lossless) {
+        (Some(e), _, _) => {
             log::error!("[scan] Analysis returned error for {:?}: {:?}", path, err);
-            "error".to_string()
+            warnings.push(ScanWarning::new(ScanWarningCode::AnalyzerFailed, e.clone()));
+            ScanStatus::Error
         }
-        (false, Some(b), _) if b < min => "bad".to_string(),
-        (false, Some(_), _) => "ok".to_string(),
-        (false, None, Some(true)) => "ok".to_string(), // FLAC/lossless = ok
+        (None, Some(b), _) if b < min => ScanStatus::Bad,
+        (None, Some(_), _) => ScanStatus::Ok,
+        (None, None, Some(true)) => ScanStatus::Ok, // FLAC/lossless = ok
         _ => {
             log::error!("[scan] No bitrate returned for {:?}, parsed: {:?}", path, parsed);
-            "error".to_string()
+            warnings.push(ScanWarning::new(
+                ScanWarningCode::NoBitrate,
+                "L'analyse n'a pas estimé de débit",
+            ));
+            ScanStatus::Error
         }
     };
 
     // Only cache if analysis was successful (has bitrate OR is lossless)
     // AND there was no error
     let analysis_successful = (est.is_some() || lossless.unwrap_or(false)) && err.is_none();
+    let clipping = if settings.detect_clipping && status.is_analyzed() {
+        scan_clipping(path, app, settings)
+    } else {
+        None
+    };
+
+    if settings.embed_analysis && analysis_successful && crate::tagging::supports_tagging(path) {
+        let embedded = CacheEntry {
+            bitrate: est,
+            is_lossless: lossless,
+            note: None,
+            cutoff_hz,
+            analyzer_version: analyzer_version.clone(),
+            analysis_offset: Some(settings.analysis_offset_seconds),
+            analysis_window: Some(analysis_window),
+            ..Default::default()
+        };
+        match crate::tagging::write_analysis_tag(path, &embedded) {
+            // The tag changed the file's bytes: key the entry on what the next scan
+            // will hash, not on the file as it was before
+            Ok(true) if hash.is_some() => match file_hash(path) {
+                Ok(h) => hash = Some(h),
+                Err(e) => {
+                    log::warn!("[scan] Could not hash {:?} after embedding: {}", path, e);
+                    hash = None;
+                }
+            },
+            Ok(_) => {}
+            Err(e) => log::warn!("[scan] Could not embed analysis in {:?}: {}", path, e),
+        }
+    }
 
     if cache_enabled && analysis_successful {
-        if let Some(h) = hash {
-            if let Ok(mut guard) = cache.lock() {
-                guard.insert(
-                    h,
-                    CacheEntry {
-                        bitrate: est,
-                        is_lossless: lossless,
-                        note: err.clone(),
-                    },
-                );
-                enforce_cache_limit(&mut *guard, 10_000);
-            }
+        if let Some(h) = &hash {
+            let mut guard = lock_cache(cache);
+            guard.insert(
+                h.clone(),
+                CacheEntry {
+                    bitrate: est,
+                    is_lossless: lossless,
+                    note: err.clone(),
+                    cutoff_hz,
+                    analyzer_version,
+                    path: Some(path.to_string_lossy().to_string()),
+                    stream: stream_probed.then(|| stream.clone()),
+                    analysis_offset: Some(settings.analysis_offset_seconds),
+                    analysis_window: Some(analysis_window),
+                    clipping,
+                },
+            );
+            enforce_cache_limit(&mut guard, settings.cache_max_entries);
         }
     }
 
-    Ok((est, lossless, err, status))
+    let note = with_container_check(err, est, lossless, &stream);
+    warnings.extend(stream_warnings(stream_probed, est, lossless, &stream));
+    Ok(Analysis {
+        bitrate: est,
+        is_lossless: lossless,
+        cutoff_hz,
+        note,
+        status,
+        stream,
+        warnings,
+        cached: false,
+        cache_missed,
+        hash,
+        clipping,
+    })
+}
+
+/// Whether a cache entry was produced by the current analyzer. Entries without a version
+/// predate versioning and follow `accept_unversioned`; an unknown current version
+/// keeps every entry rather than rescanning everything.
+fn cache_entry_current(entry: &CacheEntry, current: Option<&str>, accept_unversioned: bool) -> bool {
+    match (entry.analyzer_version.as_deref(), current) {
+        (None, _) => accept_unversioned,
+        (Some(version), Some(current)) => version == current,
+        (Some(_), None) => true,
+    }
+}
+
+/// Whether a cache entry was analyzed over the offset and window a scan would use now.
+/// Entries written before these were recorded follow `accept_unversioned`, like
+/// entries without an analyzer version.
+fn cache_entry_window_matches(entry: &CacheEntry, offset: u32, window: u32, accept_unversioned: bool) -> bool {
+    match (entry.analysis_offset, entry.analysis_window) {
+        (Some(o), Some(w)) => o == offset && w == window,
+        _ => accept_unversioned,
+    }
+}
+
+/// Append a bitrate-disagreement note for lossy files when the container bitrate is known
+fn with_container_check(
+    note: Option<String>,
+    bitrate: Option<u32>,
+    is_lossless: Option<bool>,
+    stream: &StreamInfo,
+) -> Option<String> {
+    if is_lossless == Some(true) {
+        return note;
+    }
+    let disagreement = match (bitrate, stream.container_bitrate) {
+        (Some(analyzed), Some(container)) => bitrate_disagreement(analyzed, container),
+        _ => None,
+    };
+    match (note, disagreement) {
+        (Some(n), Some(d)) => Some(format!("{} | {}", n, d)),
+        (n, d) => n.or(d),
+    }
 }
 
 /// Simple quality analysis result for single files
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct QualityAnalysisResult {
     pub bitrate: Option<u32>,
     pub is_lossless: Option<bool>,
     pub quality_string: String,
+    /// Spectral cutoff in Hz
+    pub cutoff: Option<u32>,
     pub error: Option<String>,
 }
 
-/// Analyze a single file's quality without caching (for downloads)
-/// Returns bitrate, lossless flag, and a quality display string
+/// Bitrate (kbps), lossless flag and spectral cutoff (Hz) from an analyzer JSON answer
+pub fn estimate_fields(parsed: &serde_json::Value) -> (Option<u32>, Option<bool>, Option<u32>) {
+    let rounded = |key: &str| parsed.get(key).and_then(|v| v.as_f64()).map(|v| v.round() as u32);
+    (
+        rounded("estimated_bitrate_numeric"),
+        parsed.get("is_lossless").and_then(|v| v.as_bool()),
+        rounded("max_frequency"),
+    )
+}
+
+/// Analyzer runs in flight at once for analyze_windows
+const MAX_CONCURRENT_WINDOWS: usize = 3;
+
+fn analyze_window(
+    app: &tauri::AppHandle,
+    settings: &crate::settings::Settings,
+    path_str: &str,
+    window: u32,
+) -> WindowAnalysis {
+    let parsed = match tauri::async_runtime::block_on(invoke_whatsmybitrate(
+        app,
+        settings,
+        "analyze",
+        path_str,
+        Some(window),
+        None,
+        None,
+    )) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return WindowAnalysis {
+                window_seconds: window,
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        }
+    };
+
+    let (bitrate, is_lossless, cutoff_hz) = estimate_fields(&parsed);
+    WindowAnalysis {
+        window_seconds: window,
+        bitrate,
+        is_lossless,
+        cutoff_hz,
+        error: parsed
+            .get("error")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string()),
+    }
+}
+
+/// Run the analyzer once per window (uncached, a few at a time) so the stability of the
+/// estimate across window lengths can be judged. Results follow the order of `windows`.
+pub fn analyze_windows(
+    path: &Path,
+    app: &tauri::AppHandle,
+    windows: &[u32],
+) -> Result<Vec<WindowAnalysis>, String> {
+    let path_str = path_arg(path)?;
+    let settings = crate::settings::load_settings(app);
+    let settings = &settings;
+    let mut results = Vec::with_capacity(windows.len());
+
+    for chunk in windows.chunks(MAX_CONCURRENT_WINDOWS) {
+        std::thread::scope(|s| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|&w| (w, s.spawn(move || analyze_window(app, settings, path_str, w))))
+                .collect();
+            for (w, handle) in handles {
+                results.push(handle.join().unwrap_or_else(|_| WindowAnalysis {
+                    window_seconds: w,
+                    error: Some("Analysis thread panicked".to_string()),
+                    ..Default::default()
+                }));
+            }
+        });
+    }
+    Ok(results)
+}
+
+/// Analyze a single file's quality without caching (for downloads), over the file's
+/// analysis window from settings. Returns bitrate, lossless flag, and a quality display string
 pub fn analyze_file_quality(path: &Path, app: &tauri::AppHandle) -> Result<QualityAnalysisResult, String> {
     // Use a dummy cache since we don't need caching for single downloads
     let dummy_cache = Arc::new(Mutex::new(HashMap::new()));
+    let settings = crate::settings::load_settings(app);
+    let (_, window) = settings.thresholds_for(path, None, None);
     
-    let (bitrate, is_lossless, error, _status) = analyze_with_wmb_single(
+    let analysis = analyze_with_wmb_single(
         path,
         app,
+        &settings,
         0, // min_kbps - we don't filter, just analyze
-        30, // analysis_window seconds
+        window,
         false, // cache_enabled
         &dummy_cache,
     )?;
     
     // Build quality display string
-    let quality_string = match (bitrate, is_lossless) {
+    let quality_string = match (analysis.bitrate, analysis.is_lossless) {
         (Some(br), Some(true)) => format!("{} kbps (Lossless)", br),
         (Some(br), _) => format!("{} kbps", br),
+        (None, Some(true)) => "Lossless".to_string(),
         (None, _) => "Unknown".to_string(),
     };
     
     Ok(QualityAnalysisResult {
-        bitrate,
-        is_lossless,
+        bitrate: analysis.bitrate,
+        is_lossless: analysis.is_lossless,
         quality_string,
-        error,
+        cutoff: analysis.cutoff_hz,
+        error: analysis.note,
     })
 }
+
+/// Whether a downloaded candidate is worth replacing the original with: it is lossless,
+/// or it beats the original's estimated bitrate without a lower spectral cutoff.
+/// `original` is None when the original couldn't be analyzed.
+pub fn is_quality_upgrade(
+    original: Option<&QualityAnalysisResult>,
+    candidate: &QualityAnalysisResult,
+) -> bool {
+    if candidate.is_lossless == Some(true) {
+        return true;
+    }
+    if original.is_some_and(|o| o.is_lossless == Some(true)) {
+        return false;
+    }
+    let higher_bitrate = match (candidate.bitrate, original.and_then(|o| o.bitrate)) {
+        (Some(c), Some(o)) => c > o,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    let cutoff_kept = match (candidate.cutoff, original.and_then(|o| o.cutoff)) {
+        (Some(c), Some(o)) => c >= o,
+        (None, Some(_)) => false,
+        (_, None) => true,
+    };
+    higher_bitrate && cutoff_kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic_failures_are_not_retried() {
+        assert!(is_deterministic_failure("File not found: /music/a.mp3"));
+        assert!(is_deterministic_failure("whatsmybitrate (python) execution failed: No such file or directory"));
+        assert!(!is_deterministic_failure("Failed to parse output (binary): EOF"));
+        assert!(!is_deterministic_failure(""));
+    }
+
+    #[test]
+    fn test_bitrate_disagreement_threshold() {
+        assert!(bitrate_disagreement(320, 320).is_none());
+        assert!(bitrate_disagreement(250, 320).is_none());
+        assert!(bitrate_disagreement(128, 320).is_some());
+        assert!(bitrate_disagreement(320, 0).is_none());
+    }
+
+    #[test]
+    fn test_is_audio_matches_configured_extensions_case_insensitively() {
+        let extensions = vec!["mp3".to_string(), ".AIFF".to_string()];
+        assert!(is_audio(Path::new("/music/a.MP3"), &extensions));
+        assert!(is_audio(Path::new("/music/b.aiff"), &extensions));
+        assert!(!is_audio(Path::new("/music/c.flac"), &extensions));
+        assert!(!is_audio(Path::new("/music/noext"), &extensions));
+    }
+
+    #[test]
+    fn test_file_hash_reports_progress_and_stops_when_cancelled() {
+        let path = std::env::temp_dir().join(format!("ksi_hash_test_{}.bin", std::process::id()));
+        fs::write(&path, vec![7u8; HASH_BUFFER_BYTES + 10]).unwrap();
+
+        let read = AtomicU64::new(0);
+        let hash = file_hash_with_progress(&path, Some(&read), None).unwrap();
+        assert_eq!(hash, file_hash(&path).unwrap());
+        assert_eq!(read.load(Ordering::SeqCst), HASH_BUFFER_BYTES as u64 + 10);
+
+        let cancel = AtomicBool::new(true);
+        let err = file_hash_with_progress(&path, None, Some(&cancel)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_is_quality_upgrade_requires_lossless_or_better_bitrate_and_cutoff() {
+        let quality = |bitrate: Option<u32>, cutoff: Option<u32>, lossless: bool| QualityAnalysisResult {
+            bitrate,
+            is_lossless: Some(lossless),
+            quality_string: String::new(),
+            cutoff,
+            error: None,
+        };
+        let original = quality(Some(192), Some(19000), false);
+
+        assert!(is_quality_upgrade(Some(&original), &quality(None, None, true)));
+        assert!(is_quality_upgrade(Some(&original), &quality(Some(320), Some(20000), false)));
+        assert!(!is_quality_upgrade(Some(&original), &quality(Some(320), Some(16000), false)));
+        assert!(!is_quality_upgrade(Some(&original), &quality(Some(128), Some(20000), false)));
+        assert!(!is_quality_upgrade(Some(&quality(None, None, true)), &quality(Some(320), Some(20000), false)));
+        assert!(is_quality_upgrade(None, &quality(Some(320), None, false)));
+    }
+
+    #[test]
+    fn test_resolve_resource_covers_bundle_and_dev_layouts() {
+        let root = std::env::temp_dir().join(format!("ksi_resource_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let res = root.join("app/res");
+        let exe = root.join("app/target/debug");
+        fs::create_dir_all(&exe).unwrap();
+
+        // Dev: vendor sources next to the build directory
+        let dev = root.join("app/target/vendor/whatsmybitrate");
+        fs::create_dir_all(&dev).unwrap();
+        fs::write(dev.join("requirements.txt"), "numpy\n").unwrap();
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "whatsmybitrate"), Some(exe.join("../vendor/whatsmybitrate")));
+        assert_eq!(
+            resolve_resource_file_in(Some(&res), Some(&exe), "whatsmybitrate", "requirements.txt"),
+            Some(exe.join("../vendor/whatsmybitrate/requirements.txt"))
+        );
+
+        // Bundled ../vendor copied under _up_
+        let up = res.join("_up_/vendor/whatsmybitrate");
+        fs::create_dir_all(&up).unwrap();
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "whatsmybitrate"), Some(up.clone()));
+        // ...but a file only the dev checkout has is still found there
+        assert_eq!(
+            resolve_resource_file_in(Some(&res), Some(&exe), "whatsmybitrate", "requirements.txt"),
+            Some(exe.join("../vendor/whatsmybitrate/requirements.txt"))
+        );
+
+        // resources/ subdir wins over everything else
+        let nested = res.join("resources/whatsmybitrate");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "whatsmybitrate"), Some(nested));
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "missing"), None);
+
+        // Sidecars: resource_dir/binaries/ in dev, next to the executable once bundled
+        fs::create_dir_all(res.join("binaries")).unwrap();
+        fs::write(res.join("binaries/ffprobe"), "").unwrap();
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "ffprobe"), Some(res.join("binaries/ffprobe")));
+        fs::write(exe.join("ffprobe"), "").unwrap();
+        assert_eq!(resolve_resource_in(Some(&res), Some(&exe), "ffprobe"), Some(exe.join("ffprobe")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_analysis_failure_warning_classifies_missing_analyzer() {
+        let missing = analysis_failure_warning(&AnalysisError {
+            message: "Bundled whatsmybitrate not found and dev script missing".into(),
+            code: Some(ScanWarningCode::AnalyzerMissing),
+            ..Default::default()
+        });
+        assert_eq!(missing.code, ScanWarningCode::AnalyzerMissing);
+        let failed = analysis_failure_warning(&AnalysisError::from(
+            "Failed to parse output (binary): EOF".to_string(),
+        ));
+        assert_eq!(failed.code, ScanWarningCode::AnalyzerFailed);
+        assert_eq!(failed.message, "Failed to parse output (binary): EOF");
+        // A file path that happens to look like a resolver message isn't a missing analyzer
+        let odd_name = analysis_failure_warning(&AnalysisError::from(
+            "File not found: /music/(analyzer_mode=binary).mp3".to_string(),
+        ));
+        assert_eq!(odd_name.code, ScanWarningCode::AnalyzerFailed);
+
+        assert!(analyzer_unavailable(&missing));
+        let deps = AnalyzerError::from("ModuleNotFoundError: No module named 'librosa'".to_string());
+        assert!(matches!(deps, AnalyzerError::DepsMissing(_)));
+        assert!(analyzer_unavailable(&ScanWarning::new(
+            ScanWarningCode::AnalyzerDepsMissing,
+            deps.to_string(),
+        )));
+        assert!(!analyzer_unavailable(&failed));
+        assert!(!analyzer_unavailable(&odd_name));
+    }
+
+    #[test]
+    fn test_spectrogram_options_validated_and_passed_through() {
+        assert!(validate_spectrogram_options(&SpectrogramOptions::default()).is_ok());
+        assert!(validate_spectrogram_options(&SpectrogramOptions {
+            height: Some(50),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_spectrogram_options(&SpectrogramOptions {
+            max_freq_khz: Some(f64::NAN),
+            ..Default::default()
+        })
+        .is_err());
+
+        let args = spectrogram_args(&SpectrogramOptions {
+            width: Some(1600),
+            height: None,
+            max_freq_khz: Some(22.05),
+        });
+        assert_eq!(args, vec!["--width", "1600", "--max-freq", "22.05"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_abnormal_exit_names_the_signal() {
+        use std::os::unix::process::ExitStatusExt;
+        let segfault = std::process::ExitStatus::from_raw(11);
+        assert_eq!(abnormal_exit(&segfault).as_deref(), Some("killed by signal 11 (SIGSEGV)"));
+        let clean_failure = std::process::ExitStatus::from_raw(1 << 8);
+        assert_eq!(abnormal_exit(&clean_failure), None);
+    }
+
+    #[test]
+    fn test_cache_entries_from_another_analyzer_version_are_stale() {
+        let entry = |version: Option<&str>| CacheEntry {
+            bitrate: Some(320),
+            is_lossless: Some(false),
+            note: None,
+            cutoff_hz: None,
+            analyzer_version: version.map(|v| v.to_string()),
+            ..Default::default()
+        };
+        assert!(cache_entry_current(&entry(Some("1.1.0")), Some("1.1.0"), false));
+        assert!(!cache_entry_current(&entry(Some("1.0.0")), Some("1.1.0"), true));
+        assert!(cache_entry_current(&entry(None), Some("1.1.0"), true));
+        assert!(!cache_entry_current(&entry(None), Some("1.1.0"), false));
+        assert!(cache_entry_current(&entry(Some("1.0.0")), None, false));
+    }
+
+    #[test]
+    fn test_cache_entries_over_another_window_are_stale() {
+        let entry = |window: Option<(u32, u32)>| CacheEntry {
+            bitrate: Some(320),
+            analysis_offset: window.map(|w| w.0),
+            analysis_window: window.map(|w| w.1),
+            ..Default::default()
+        };
+        assert!(cache_entry_window_matches(&entry(Some((0, 30))), 0, 30, false));
+        assert!(!cache_entry_window_matches(&entry(Some((0, 30))), 15, 30, true));
+        assert!(!cache_entry_window_matches(&entry(Some((0, 30))), 0, 60, true));
+        assert!(cache_entry_window_matches(&entry(None), 15, 60, true));
+        assert!(!cache_entry_window_matches(&entry(None), 0, 30, false));
+    }
+
+    #[test]
+    fn test_prefilter_passes_only_lossless_and_clearly_high_bitrates() {
+        let stream = |codec: &str, kbps: Option<u32>| StreamInfo {
+            codec: Some(codec.to_string()),
+            container_bitrate: kbps,
+            ..Default::default()
+        };
+        assert_eq!(prefilter_verdict(&stream("flac", Some(900)), 256).map(|v| v.0), Some(true));
+        assert_eq!(prefilter_verdict(&stream("pcm_s16le", None), 256).map(|v| v.0), Some(true));
+        assert_eq!(prefilter_verdict(&stream("mp3", Some(320)), 256).map(|v| v.0), Some(false));
+        assert!(prefilter_verdict(&stream("mp3", Some(256)), 256).is_none());
+        assert!(prefilter_verdict(&stream("aac", None), 256).is_none());
+        assert!(prefilter_verdict(&StreamInfo::default(), 256).is_none());
+    }
+
+    #[test]
+    fn test_warmup_announces_once_and_clears_on_first_answer() {
+        let warmup = Warmup::default();
+        assert!(warmup.start());
+        assert!(!warmup.start()); // concurrent first calls announce only once
+        assert!(warmup.finish());
+        assert!(!warmup.finish());
+        assert!(!warmup.start());
+    }
+
+    #[test]
+    fn test_pick_analyzer_honors_mode() {
+        let bundled = || Some("binary");
+        let missing = || None::<&str>;
+        let python = || Some("python");
+        assert_eq!(pick_analyzer("auto", bundled, python), Ok("binary"));
+        assert_eq!(pick_analyzer("auto", missing, python), Ok("python"));
+        assert_eq!(pick_analyzer("python", bundled, python), Ok("python"));
+        assert!(matches!(pick_analyzer("binary", missing, python), Err(AnalyzerError::Missing(_))));
+    }
+
+    #[test]
+    fn test_analysis_window_and_offset_reach_the_argv() {
+        let settings = crate::settings::Settings {
+            analysis_window_seconds: 90,
+            analysis_offset_seconds: 15,
+            ..Default::default()
+        };
+        let path = Path::new("/music/a.mp3");
+        let (_, window) = settings.thresholds_for(path, None, None);
+        let args = analyzer_args(
+            "analyze",
+            "/music/a.mp3",
+            Some(window),
+            Some(settings.analysis_offset_seconds),
+            None,
+            None,
+        );
+        assert_eq!(
+            args,
+            ["analyze", "/music/a.mp3", "--window", "90", "--offset", "15"]
+        );
+        // A zero offset is left out for analyzer builds without --offset
+        assert!(!analyzer_args("analyze", "/music/a.mp3", Some(30), Some(0), None, None)
+            .contains(&"--offset".to_string()));
+    }
+
+    #[test]
+    fn test_analyzer_slots_bound_concurrency() {
+        let slots = AnalyzerSlots::new();
+        let running = std::sync::atomic::AtomicUsize::new(0);
+        let peak = std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let _slot = slots.acquire(2);
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert_eq!(*slots.lock(), 0);
+        assert_eq!(analyzer_slot_limit(3), 3);
+        assert!(analyzer_slot_limit(0) >= 1);
+    }
+
+    #[test]
+    fn test_truncation_note_flags_empty_tiny_and_zero_length_files() {
+        assert_eq!(truncation_note(Some(0), None).as_deref(), Some("Fichier vide (0 octet)"));
+        assert_eq!(truncation_note(Some(400), None).as_deref(), Some("Fichier tronqué (400 octets)"));
+        assert!(truncation_note(None, Some(0.0)).is_some());
+        assert_eq!(truncation_note(Some(4_000_000), Some(215.3)), None);
+        assert_eq!(truncation_note(None, None), None);
+    }
+
+    #[test]
+    fn test_parse_max_volume_reads_volumedetect_summary() {
+        let stderr = "[Parsed_volumedetect_0 @ 0x1] n_samples: 2646000\n\
+            [Parsed_volumedetect_0 @ 0x1] mean_volume: -14.2 dB\n\
+            [Parsed_volumedetect_0 @ 0x1] max_volume: -0.0 dB\n";
+        let peak = parse_max_volume(stderr).unwrap();
+        assert!(peak >= CLIPPING_PEAK_DB);
+        assert_eq!(parse_max_volume("max_volume: -3.1 dB"), Some(-3.1));
+        assert_eq!(parse_max_volume("no summary"), None);
+    }
+}
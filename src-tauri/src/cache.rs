@@ -1,17 +1,18 @@
 use std::collections::HashMap;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use serde::Serialize;
 use tauri::Manager;
 
+use crate::results::{histogram_of, HistogramBucket};
 use crate::types::CacheEntry;
 
 pub fn cache_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let base = app
-        .path()
-        .app_data_dir()
-        .or_else(|_| app.path().app_cache_dir())
-        .map_err(|e| e.to_string())?;
+    let base = crate::settings::data_dir(app);
 
     let path = base.join("analysis-cache.json");
     if let Some(parent) = path.parent() {
@@ -20,6 +21,95 @@ pub fn cache_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(path)
 }
 
+type SharedCache = Arc<Mutex<HashMap<String, CacheEntry>>>;
+
+/// In-memory caches of the analyses in progress, flushed if the app exits before
+/// they finish and save themselves
+#[derive(Default)]
+pub struct ActiveCaches(Mutex<Vec<(PathBuf, SharedCache)>>);
+
+impl ActiveCaches {
+    pub fn register(&self, path: &Path, cache: &SharedCache) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((path.to_path_buf(), Arc::clone(cache)));
+    }
+
+    pub fn unregister(&self, cache: &SharedCache) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(_, c)| !Arc::ptr_eq(c, cache));
+    }
+
+    /// Whether a scan currently holds a cache in memory
+    pub fn is_busy(&self) -> bool {
+        !self.0.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    /// Save every registered cache to disk
+    pub fn flush(&self) {
+        for (path, cache) in self.0.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            match save_cache(path, &lock_cache(cache)) {
+                Ok(()) => log::info!("[cache] Flushed in-progress cache to {:?}", path),
+                Err(e) => log::warn!("[cache] Failed to flush cache: {}", e),
+            }
+        }
+    }
+}
+
+/// Saves a cache that is being filled during a long scan every `every_files` analyzed
+/// files or `every` elapsed, whichever comes first, so an abnormal exit loses little.
+/// Only one flush runs at a time; callers that hit the threshold meanwhile skip it.
+pub struct PeriodicFlush {
+    every_files: usize,
+    every: Duration,
+    since_flush: AtomicUsize,
+    last_flush: Mutex<Instant>,
+    flushing: AtomicBool,
+}
+
+impl PeriodicFlush {
+    pub fn new(every_files: usize, every: Duration) -> Self {
+        PeriodicFlush {
+            every_files,
+            every,
+            since_flush: AtomicUsize::new(0),
+            last_flush: Mutex::new(Instant::now()),
+            flushing: AtomicBool::new(false),
+        }
+    }
+
+    /// Count one analyzed file and flush if a threshold was reached. Returns whether
+    /// this call flushed.
+    pub fn file_done(&self, path: &Path, cache: &Mutex<HashMap<String, CacheEntry>>) -> bool {
+        let count = self.since_flush.fetch_add(1, Ordering::SeqCst) + 1;
+        let due = count >= self.every_files
+            || self.last_flush.lock().unwrap_or_else(|e| e.into_inner()).elapsed() >= self.every;
+        if !due
+            || self
+                .flushing
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+        {
+            return false;
+        }
+
+        self.since_flush.store(0, Ordering::SeqCst);
+        *self.last_flush.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        // Snapshot so analysis threads aren't blocked on the disk write
+        let snapshot = lock_cache(cache).clone();
+        if let Err(e) = save_cache(path, &snapshot) {
+            log::warn!("[cache] Periodic flush failed: {}", e);
+        } else {
+            log::debug!("[cache] Flushed {} entries mid-scan", snapshot.len());
+        }
+        self.flushing.store(false, Ordering::SeqCst);
+        true
+    }
+}
+
 pub fn load_cache(path: &Path, limit: usize) -> HashMap<String, CacheEntry> {
     if let Ok(text) = fs::read_to_string(path) {
         let mut map: HashMap<String, CacheEntry> = serde_json::from_str(&text).unwrap_or_default();
@@ -30,16 +120,151 @@ pub fn load_cache(path: &Path, limit: usize) -> HashMap<String, CacheEntry> {
     }
 }
 
+/// Width of the bitrate bars in library_stats
+const LIBRARY_STATS_BUCKET_KBPS: u32 = 32;
+
+/// Aggregate figures over every cached analysis. The cache is keyed by content hash,
+/// so duplicates count once and deleted files linger until evicted.
+#[derive(Serialize, Debug, Default)]
+pub struct LibraryStats {
+    pub analyzed: usize,
+    pub lossless: usize,
+    pub lossy: usize,
+    /// Mean estimated bitrate of the lossy entries, in kbps
+    pub average_lossy_bitrate: Option<u32>,
+    pub bitrate_distribution: Vec<HistogramBucket>,
+    /// Entries saved with an analyzer error note
+    pub with_error_note: usize,
+}
+
+pub fn library_stats_of(cache: &HashMap<String, CacheEntry>) -> LibraryStats {
+    let lossless = cache.values().filter(|e| e.is_lossless == Some(true)).count();
+    let lossy_bitrates: Vec<u64> = cache
+        .values()
+        .filter(|e| e.is_lossless != Some(true))
+        .filter_map(|e| e.bitrate.map(u64::from))
+        .collect();
+    LibraryStats {
+        analyzed: cache.len(),
+        lossless,
+        lossy: cache.len() - lossless,
+        average_lossy_bitrate: (!lossy_bitrates.is_empty())
+            .then(|| (lossy_bitrates.iter().sum::<u64>() / lossy_bitrates.len() as u64) as u32),
+        bitrate_distribution: histogram_of(
+            cache.values().map(|e| (e.is_lossless, e.bitrate)),
+            LIBRARY_STATS_BUCKET_KBPS,
+        ),
+        with_error_note: cache.values().filter(|e| e.note.is_some()).count(),
+    }
+}
+
+/// Library overview computed from the analysis cache alone, without walking any folder
+#[tauri::command]
+pub async fn library_stats(app: tauri::AppHandle) -> Result<LibraryStats, String> {
+    let path = cache_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = load_cache(&path, usize::MAX);
+        library_stats_of(&cache)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Drop the entries whose recorded path fails `exists`, returning how many went.
+/// Entries without a recorded path are kept: there is nothing to check them against.
+pub fn prune_missing(cache: &mut HashMap<String, CacheEntry>, exists: impl Fn(&Path) -> bool) -> usize {
+    let before = cache.len();
+    cache.retain(|_, entry| entry.path.as_deref().is_none_or(|p| exists(Path::new(p))));
+    before - cache.len()
+}
+
+/// Remove the cache entries of files that no longer exist at their recorded path.
+/// Refused while a scan runs, since the scan would write its own copy back; the scan
+/// slot is held for the whole prune so none can start meanwhile.
+#[tauri::command]
+pub async fn prune_cache(app: tauri::AppHandle) -> Result<usize, String> {
+    let path = cache_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let tracker = app.state::<crate::scan_state::ScanTracker>();
+        let _scan = tracker
+            .try_start()
+            .ok_or_else(|| "Une analyse est en cours, réessayez après".to_string())?;
+        if app.state::<ActiveCaches>().is_busy() {
+            return Err("Une analyse est en cours, réessayez après".into());
+        }
+        let mut cache = load_cache(&path, usize::MAX);
+        let removed = prune_missing(&mut cache, |p| p.exists());
+        if removed > 0 {
+            save_cache(&path, &cache).map_err(|e| e.to_string())?;
+        }
+        log::info!("[cache] Pruned {} entries of missing files", removed);
+        Ok(removed)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Lock the shared analysis cache. A panic in another analysis thread poisons the
+/// mutex; the map itself is still usable, so recover it instead of dropping caching
+/// for the rest of the scan.
+pub fn lock_cache(
+    cache: &Mutex<HashMap<String, CacheEntry>>,
+) -> MutexGuard<'_, HashMap<String, CacheEntry>> {
+    cache.lock().unwrap_or_else(|poisoned| {
+        log::warn!("[cache] Cache lock was poisoned by a panicked thread, recovering");
+        poisoned.into_inner()
+    })
+}
+
 pub fn save_cache(path: &Path, cache: &HashMap<String, CacheEntry>) -> io::Result<()> {
+    write_atomic(path, serde_json::to_string(cache).unwrap_or_default().as_bytes())
+}
+
+/// Write `contents` to a uniquely named temp file next to `path`, fsync it, then move it
+/// over `path`. Concurrent writers never share a temp file, and a crash leaves either the
+/// old or the new file, never a truncated one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let tmp = path.with_extension("tmp");
-    fs::write(&tmp, serde_json::to_string(cache).unwrap_or_default())?;
-    fs::rename(tmp, path)?;
+    let tmp = path.with_extension(format!(
+        "{}.{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let written = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    })();
+    if let Err(e) = written.and_then(|_| replace_file(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
     Ok(())
 }
 
+#[cfg(not(target_os = "windows"))]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+}
+
+/// rename can fail on Windows when the target exists (e.g. flagged read-only or left
+/// behind by another process); remove it and retry once
+#[cfg(target_os = "windows")]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(_) if to.exists() => {
+            fs::remove_file(to)?;
+            fs::rename(from, to)
+        }
+        result => result,
+    }
+}
+
 pub fn enforce_cache_limit(cache: &mut HashMap<String, CacheEntry>, limit: usize) {
     if limit == 0 || cache.len() <= limit {
         return;
@@ -52,3 +277,141 @@ pub fn enforce_cache_limit(cache: &mut HashMap<String, CacheEntry>, limit: usize
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_cache_recovers_from_poisoning() {
+        let cache = std::sync::Arc::new(Mutex::new(HashMap::new()));
+        let poisoner = cache.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("analysis thread panicked while holding the cache");
+        })
+        .join();
+        assert!(cache.is_poisoned());
+
+        lock_cache(&cache).insert(
+            "hash".to_string(),
+            CacheEntry {
+                bitrate: Some(256),
+                is_lossless: None,
+                note: None,
+                cutoff_hz: None,
+                analyzer_version: None,
+                ..Default::default()
+            },
+        );
+        assert_eq!(lock_cache(&cache).len(), 1);
+    }
+
+    fn entry(bitrate: u32) -> CacheEntry {
+        CacheEntry {
+            bitrate: Some(bitrate),
+            is_lossless: None,
+            note: None,
+            cutoff_hz: None,
+            analyzer_version: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_enforce_cache_limit_evicts_at_configured_size() {
+        let mut cache: HashMap<String, CacheEntry> =
+            (0..5).map(|i| (format!("hash{}", i), entry(128))).collect();
+        enforce_cache_limit(&mut cache, 3);
+        assert_eq!(cache.len(), 3);
+
+        // 0 means unlimited
+        cache.insert("extra".to_string(), entry(320));
+        enforce_cache_limit(&mut cache, 0);
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn test_save_cache_replaces_existing_file() {
+        let dir = std::env::temp_dir().join(format!("ksi_cache_test_{}", std::process::id()));
+        let path = dir.join("analysis-cache.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "stale contents").unwrap();
+
+        let mut cache = HashMap::new();
+        cache.insert(
+            "hash".to_string(),
+            CacheEntry {
+                bitrate: Some(320),
+                is_lossless: Some(false),
+                note: None,
+                cutoff_hz: Some(20_000),
+                analyzer_version: None,
+                ..Default::default()
+            },
+        );
+        save_cache(&path, &cache).unwrap();
+
+        let loaded = load_cache(&path, 0);
+        assert_eq!(loaded.get("hash").and_then(|e| e.bitrate), Some(320));
+        // No temp files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_periodic_flush_saves_every_n_files() {
+        let dir = std::env::temp_dir().join(format!("ksi_flush_test_{}", std::process::id()));
+        let path = dir.join("cache.json");
+        let cache = Mutex::new(HashMap::new());
+        let flush = PeriodicFlush::new(3, Duration::from_secs(3600));
+
+        assert!(!flush.file_done(&path, &cache));
+        assert!(!flush.file_done(&path, &cache));
+        assert!(!path.exists());
+        assert!(flush.file_done(&path, &cache));
+        assert!(path.exists());
+        assert!(!flush.file_done(&path, &cache));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_library_stats_splits_lossless_and_buckets_lossy() {
+        let mut cache: HashMap<String, CacheEntry> = HashMap::new();
+        cache.insert("a".into(), entry(128));
+        cache.insert("b".into(), entry(320));
+        cache.insert(
+            "c".into(),
+            CacheEntry {
+                is_lossless: Some(true),
+                note: Some("old failure".into()),
+                ..entry(1411)
+            },
+        );
+
+        let stats = library_stats_of(&cache);
+        assert_eq!(stats.analyzed, 3);
+        assert_eq!((stats.lossless, stats.lossy), (1, 2));
+        assert_eq!(stats.average_lossy_bitrate, Some(224));
+        assert_eq!(stats.with_error_note, 1);
+        let labels: Vec<&str> = stats.bitrate_distribution.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["128-159 kbps", "320-351 kbps", "lossless"]);
+    }
+
+    #[test]
+    fn test_prune_missing_keeps_entries_without_a_path() {
+        let mut cache = HashMap::new();
+        let with_path = |p: &str| CacheEntry {
+            path: Some(p.to_string()),
+            ..entry(320)
+        };
+        cache.insert("a".to_string(), with_path("/music/here.mp3"));
+        cache.insert("b".to_string(), with_path("/music/gone.mp3"));
+        cache.insert("c".to_string(), entry(128));
+
+        let removed = prune_missing(&mut cache, |p| p == Path::new("/music/here.mp3"));
+        assert_eq!(removed, 1);
+        assert!(cache.contains_key("a") && cache.contains_key("c"));
+        assert!(!cache.contains_key("b"));
+    }
+}
@@ -0,0 +1,272 @@
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::audio::{analyze_file_quality, extract_metadata_from_file};
+use crate::tagging::has_replaced_tag;
+use crate::types::ScanResult;
+
+/// Recordings that look like the same song, best copy first
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub artist: String,
+    pub title: String,
+    /// Suggested copy to keep: lossless first, then highest bitrate
+    pub keep: ScanResult,
+    pub remove: Vec<ScanResult>,
+}
+
+/// Lowercase, drop bracketed parts ("(Remastered)", "[Live]") and featured artists,
+/// strip punctuation and collapse whitespace
+pub fn normalize(s: &str) -> String {
+    static BRACKETS: OnceLock<Regex> = OnceLock::new();
+    static FEAT: OnceLock<Regex> = OnceLock::new();
+    let brackets = BRACKETS.get_or_init(|| Regex::new(r"[\(\[\{][^\)\]\}]*[\)\]\}]").unwrap());
+    let feat = FEAT.get_or_init(|| Regex::new(r"\b(feat|ft|featuring)\b\.?.*$").unwrap());
+
+    let lower = s.to_lowercase();
+    let without_brackets = brackets.replace_all(&lower, " ");
+    let without_feat = feat.replace(&without_brackets, " ");
+    without_feat
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Better copies sort first: lossless, then higher bitrate, then larger file
+fn rank(a: &ScanResult, b: &ScanResult) -> Ordering {
+    let lossless = |r: &ScanResult| r.is_lossless == Some(true);
+    lossless(b)
+        .cmp(&lossless(a))
+        .then_with(|| b.bitrate.cmp(&a.bitrate))
+        .then_with(|| b.size_bytes.cmp(&a.size_bytes))
+        .then_with(|| a.path.cmp(&b.path))
+}
+
+/// A result with the artist and title it was matched on
+type Tagged = (String, String, ScanResult);
+
+/// Group results sharing a normalized artist+title. Only groups of two or more are returned.
+pub fn group_duplicates(tagged: Vec<Tagged>) -> Vec<DuplicateGroup> {
+    let mut groups: HashMap<(String, String), Vec<Tagged>> = HashMap::new();
+    for (artist, title, result) in tagged {
+        let key = (normalize(&artist), normalize(&title));
+        if key.1.is_empty() {
+            continue;
+        }
+        groups.entry(key).or_default().push((artist, title, result));
+    }
+
+    let mut out: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by(|a, b| rank(&a.2, &b.2));
+            let mut members = members.into_iter();
+            let (artist, title, keep) = members.next().expect("group has members");
+            DuplicateGroup {
+                artist,
+                title,
+                keep,
+                remove: members.map(|(_, _, r)| r).collect(),
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        normalize(&a.artist)
+            .cmp(&normalize(&b.artist))
+            .then_with(|| normalize(&a.title).cmp(&normalize(&b.title)))
+    });
+    out
+}
+
+/// Find likely duplicate recordings (same song in several formats or bitrates) among
+/// scan results, using embedded tags or the file name when tags are missing
+#[tauri::command]
+pub async fn find_duplicates(
+    results: Vec<ScanResult>,
+    app: tauri::AppHandle,
+) -> Result<Vec<DuplicateGroup>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let tagged: Vec<Tagged> = results
+            .into_par_iter()
+            .map(|r| {
+                let path = Path::new(&r.path);
+                let meta = extract_metadata_from_file(path, &app);
+                let stem = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let title = meta.title.unwrap_or(stem);
+                (meta.artist.unwrap_or_default(), title, r)
+            })
+            .collect();
+        group_duplicates(tagged)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// One file of compare_files, as analyzed now
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ComparedFile {
+    pub path: String,
+    pub bitrate: Option<u32>,
+    pub is_lossless: Option<bool>,
+    /// Spectral cutoff in Hz
+    pub cutoff: Option<u32>,
+    pub quality_string: String,
+    pub size_bytes: Option<u64>,
+    /// Carries the KESON_REPLACED tag
+    pub replaced: bool,
+    /// Why the file couldn't be analyzed; such files rank last
+    pub error: Option<String>,
+}
+
+/// compare_files result, best copy first
+#[derive(Serialize, Debug)]
+pub struct Comparison {
+    /// Path of the copy to keep, None when none could be analyzed
+    pub keep: Option<String>,
+    pub ranked: Vec<ComparedFile>,
+}
+
+/// Better copies sort first: analyzed, lossless, higher bitrate, higher cutoff, then
+/// larger file, then one already marked as replaced
+fn rank_compared(a: &ComparedFile, b: &ComparedFile) -> Ordering {
+    let lossless = |f: &ComparedFile| f.is_lossless == Some(true);
+    a.error
+        .is_some()
+        .cmp(&b.error.is_some())
+        .then_with(|| lossless(b).cmp(&lossless(a)))
+        .then_with(|| b.bitrate.cmp(&a.bitrate))
+        .then_with(|| b.cutoff.cmp(&a.cutoff))
+        .then_with(|| b.size_bytes.cmp(&a.size_bytes))
+        .then_with(|| b.replaced.cmp(&a.replaced))
+        .then_with(|| a.path.cmp(&b.path))
+}
+
+pub fn rank_comparison(mut files: Vec<ComparedFile>) -> Comparison {
+    files.sort_by(rank_compared);
+    Comparison {
+        keep: files
+            .first()
+            .filter(|f| f.error.is_none())
+            .map(|f| f.path.clone()),
+        ranked: files,
+    }
+}
+
+/// Analyze candidate copies of the same song (e.g. a find_duplicates group) and rank
+/// them, to confirm which one to keep before trashing the others
+#[tauri::command]
+pub async fn compare_files(paths: Vec<String>, app: tauri::AppHandle) -> Result<Comparison, String> {
+    if paths.len() < 2 {
+        return Err("Sélectionnez au moins deux fichiers à comparer".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let files: Vec<ComparedFile> = paths
+            .into_par_iter()
+            .map(|path| {
+                let p = Path::new(&path);
+                let Ok(meta) = std::fs::metadata(p) else {
+                    return ComparedFile {
+                        path,
+                        error: Some("Fichier introuvable".into()),
+                        ..Default::default()
+                    };
+                };
+                let replaced = has_replaced_tag(p);
+                match analyze_file_quality(p, &app) {
+                    Ok(q) => ComparedFile {
+                        path,
+                        bitrate: q.bitrate,
+                        is_lossless: q.is_lossless,
+                        cutoff: q.cutoff,
+                        quality_string: q.quality_string,
+                        size_bytes: Some(meta.len()),
+                        replaced,
+                        error: None,
+                    },
+                    Err(e) => ComparedFile {
+                        path,
+                        size_bytes: Some(meta.len()),
+                        replaced,
+                        error: Some(e),
+                        ..Default::default()
+                    },
+                }
+            })
+            .collect();
+        rank_comparison(files)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_strips_feat_and_brackets() {
+        assert_eq!(normalize("Song Name (Remastered 2011)"), "song name");
+        assert_eq!(normalize("Song Name feat. Someone"), "song name");
+        assert_eq!(normalize("SONG-NAME [Live]"), "song name");
+    }
+
+    #[test]
+    fn test_group_duplicates_keeps_lossless_then_highest_bitrate() {
+        let result = |path: &str, bitrate: Option<u32>, lossless: bool| ScanResult {
+            path: path.to_string(),
+            bitrate,
+            is_lossless: Some(lossless),
+            ..Default::default()
+        };
+        let groups = group_duplicates(vec![
+            ("Artist".into(), "Song".into(), result("/a.mp3", Some(128), false)),
+            ("artist".into(), "Song (Remix Edit)".into(), result("/b.flac", None, true)),
+            ("Artist".into(), "Song ft. X".into(), result("/c.mp3", Some(320), false)),
+            ("Artist".into(), "Other".into(), result("/d.mp3", Some(320), false)),
+        ]);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].keep.path, "/b.flac");
+        let removed: Vec<&str> = groups[0].remove.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(removed, vec!["/c.mp3", "/a.mp3"]);
+    }
+
+    #[test]
+    fn test_rank_comparison_breaks_ties_by_size_then_replaced_tag() {
+        let file = |path: &str, bitrate: u32, size: u64, replaced: bool| ComparedFile {
+            path: path.to_string(),
+            bitrate: Some(bitrate),
+            is_lossless: Some(false),
+            size_bytes: Some(size),
+            replaced,
+            ..Default::default()
+        };
+        let comparison = rank_comparison(vec![
+            file("/small.mp3", 320, 8_000_000, true),
+            file("/tagged.mp3", 320, 9_000_000, true),
+            file("/plain.mp3", 320, 9_000_000, false),
+            ComparedFile {
+                path: "/broken.mp3".into(),
+                error: Some("corrupt".into()),
+                ..Default::default()
+            },
+            file("/low.mp3", 128, 20_000_000, false),
+        ]);
+
+        assert_eq!(comparison.keep.as_deref(), Some("/tagged.mp3"));
+        let order: Vec<&str> = comparison.ranked.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(order, vec!["/tagged.mp3", "/plain.mp3", "/small.mp3", "/low.mp3", "/broken.mp3"]);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Stable identifiers for command failures. The frontend translates by `code` and
+/// falls back to the French `message` when it has no translation.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    FolderNotFound,
+    FileNotFound,
+    /// The scanned folder disappeared mid-scan (network share, unplugged drive)
+    FolderUnreachable,
+    AnalyzerNotFound,
+    /// The python analyzer is present but its requirements aren't installed
+    PythonDepsMissing,
+    SpectrumFailed,
+    /// A command argument is outside its accepted range
+    InvalidArgument,
+    OpenFailed,
+    /// Another scan is already running
+    ScanInProgress,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn default_message(self) -> &'static str {
+        match self {
+            ErrorCode::FolderNotFound => "Dossier introuvable",
+            ErrorCode::FileNotFound => "Fichier introuvable",
+            ErrorCode::FolderUnreachable => "Le dossier n'est plus accessible. Scan interrompu.",
+            ErrorCode::AnalyzerNotFound => "whatsmybitrate introuvable",
+            ErrorCode::PythonDepsMissing => {
+                "Dépendances Python manquantes pour whatsmybitrate (pip install -r requirements.txt)"
+            }
+            ErrorCode::SpectrumFailed => "Échec génération spectre",
+            ErrorCode::InvalidArgument => "Paramètre invalide",
+            ErrorCode::OpenFailed => "Impossible d'ouvrir le dossier",
+            ErrorCode::ScanInProgress => "Un scan est déjà en cours",
+            ErrorCode::Internal => "Erreur interne",
+        }
+    }
+}
+
+/// Error returned by commands that expose a code, serialized as `{ code, message }`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode) -> Self {
+        Self::with_message(code, code.default_message())
+    }
+
+    pub fn with_message(code: ErrorCode, message: impl Into<String>) -> Self {
+        CommandError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Plain string errors from shared helpers become `internal`, keeping their text
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::with_message(ErrorCode::Internal, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_error_serializes_code_and_message() {
+        let json = serde_json::to_value(CommandError::new(ErrorCode::FolderNotFound)).unwrap();
+        assert_eq!(json["code"], "folder_not_found");
+        assert_eq!(json["message"], "Dossier introuvable");
+    }
+}
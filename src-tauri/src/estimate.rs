@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::types::ScanResult;
+
+/// Weight of the latest scan in the rolling per-file averages
+const SMOOTHING: f64 = 0.3;
+
+/// Rolling average time one file keeps a scan thread busy, persisted in scan_timing.json
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ScanTiming {
+    /// Seconds per file that went through the analyzer
+    pub analyzed_secs_per_file: Option<f64>,
+    /// Seconds per file answered from the cache (hash and ffprobe only)
+    pub cached_secs_per_file: Option<f64>,
+}
+
+/// Files of one kind timed during a scan
+#[derive(Default, Debug)]
+pub struct TimingSample {
+    pub files: usize,
+    pub secs: f64,
+}
+
+impl TimingSample {
+    pub fn add(&mut self, secs: f64) {
+        self.files += 1;
+        self.secs += secs;
+    }
+}
+
+fn blend(previous: Option<f64>, sample: &TimingSample) -> Option<f64> {
+    if sample.files == 0 {
+        return previous;
+    }
+    let mean = sample.secs / sample.files as f64;
+    Some(match previous {
+        Some(p) => p + SMOOTHING * (mean - p),
+        None => mean,
+    })
+}
+
+impl ScanTiming {
+    /// Fold one scan's samples into the averages; a kind with no files keeps its value
+    pub fn update(&mut self, analyzed: &TimingSample, cached: &TimingSample) {
+        self.analyzed_secs_per_file = blend(self.analyzed_secs_per_file, analyzed);
+        self.cached_secs_per_file = blend(self.cached_secs_per_file, cached);
+    }
+}
+
+fn timing_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = crate::settings::data_dir(app);
+    Ok(base.join("scan_timing.json"))
+}
+
+fn load_timing_from(path: &Path) -> ScanTiming {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn load_timing(app: &tauri::AppHandle) -> ScanTiming {
+    timing_path(app)
+        .map(|p| load_timing_from(&p))
+        .unwrap_or_default()
+}
+
+/// Update the persisted averages after a scan, logging instead of failing on IO errors
+pub fn record_timing(app: &tauri::AppHandle, analyzed: &TimingSample, cached: &TimingSample) {
+    if analyzed.files == 0 && cached.files == 0 {
+        return;
+    }
+    let saved = timing_path(app).and_then(|path| {
+        let mut timing = load_timing_from(&path);
+        timing.update(analyzed, cached);
+        let json = serde_json::to_string(&timing).map_err(|e| e.to_string())?;
+        crate::cache::write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        log::warn!("[estimate] Failed to save scan timing: {}", e);
+    }
+}
+
+/// What estimate_scan expects a scan of a folder to cost
+#[derive(Serialize, Debug)]
+pub struct ScanEstimate {
+    pub files: usize,
+    /// Files matching, by path and size, an analyzed row of the folder's last saved scan
+    pub expected_cached: usize,
+    pub expected_uncached: usize,
+    /// None until past scans have timed every kind of file this one needs
+    pub estimated_seconds: Option<f64>,
+    pub analyzed_secs_per_file: Option<f64>,
+    pub cached_secs_per_file: Option<f64>,
+    /// Scan threads the per-file times are spread over
+    pub threads: usize,
+}
+
+/// Estimate a scan of `entries`. `previous` holds the rows of the folder's last scan
+/// (empty when the cache is off): files it analyzed with the same size are expected to
+/// hit the cache. Per-file times are thread-busy times, so they're divided by `threads`.
+pub fn estimate_scan_of(
+    timing: &ScanTiming,
+    entries: &[(PathBuf, u64)],
+    previous: &[ScanResult],
+    threads: usize,
+) -> ScanEstimate {
+    let analyzed: HashSet<(&str, u64)> = previous
+        .iter()
+        .filter(|r| r.status.is_analyzed())
+        .filter_map(|r| r.size_bytes.map(|size| (r.path.as_str(), size)))
+        .collect();
+    let expected_cached = entries
+        .iter()
+        .filter(|(path, size)| {
+            path.to_str()
+                .is_some_and(|p| analyzed.contains(&(p, *size)))
+        })
+        .count();
+    let expected_uncached = entries.len() - expected_cached;
+
+    let cost = |files: usize, per_file: Option<f64>| {
+        if files == 0 {
+            Some(0.0)
+        } else {
+            per_file.map(|secs| secs * files as f64)
+        }
+    };
+    let threads = threads.max(1);
+    let estimated_seconds = cost(expected_uncached, timing.analyzed_secs_per_file)
+        .zip(cost(expected_cached, timing.cached_secs_per_file))
+        .map(|(analyzed, cached)| (analyzed + cached) / threads as f64);
+
+    ScanEstimate {
+        files: entries.len(),
+        expected_cached,
+        expected_uncached,
+        estimated_seconds,
+        analyzed_secs_per_file: timing.analyzed_secs_per_file,
+        cached_secs_per_file: timing.cached_secs_per_file,
+        threads,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ScanStatus;
+
+    #[test]
+    fn test_estimate_uses_rolling_averages_and_last_scan() {
+        let mut timing = ScanTiming::default();
+        timing.update(
+            &TimingSample { files: 2, secs: 8.0 },
+            &TimingSample { files: 0, secs: 0.0 },
+        );
+        assert_eq!(timing.analyzed_secs_per_file, Some(4.0));
+        timing.update(
+            &TimingSample { files: 1, secs: 14.0 },
+            &TimingSample { files: 4, secs: 2.0 },
+        );
+        assert_eq!(timing.analyzed_secs_per_file, Some(7.0));
+        assert_eq!(timing.cached_secs_per_file, Some(0.5));
+
+        let previous = vec![
+            ScanResult {
+                path: "/music/a.mp3".into(),
+                status: ScanStatus::Ok,
+                size_bytes: Some(100),
+                ..Default::default()
+            },
+            ScanResult {
+                path: "/music/b.mp3".into(),
+                status: ScanStatus::Ok,
+                size_bytes: Some(200),
+                ..Default::default()
+            },
+            ScanResult {
+                path: "/music/c.mp3".into(),
+                status: ScanStatus::Error,
+                size_bytes: Some(300),
+                ..Default::default()
+            },
+        ];
+        // b changed size since, c failed last time
+        let entries = vec![
+            (PathBuf::from("/music/a.mp3"), 100),
+            (PathBuf::from("/music/b.mp3"), 250),
+            (PathBuf::from("/music/c.mp3"), 300),
+        ];
+        let estimate = estimate_scan_of(&timing, &entries, &previous, 2);
+        assert_eq!(estimate.expected_cached, 1);
+        assert_eq!(estimate.expected_uncached, 2);
+        assert_eq!(estimate.estimated_seconds, Some((2.0 * 7.0 + 0.5) / 2.0));
+
+        let unknown = estimate_scan_of(&ScanTiming::default(), &entries, &previous, 2);
+        assert_eq!(unknown.estimated_seconds, None);
+    }
+}
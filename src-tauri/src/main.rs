@@ -1,28 +1,43 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod analyzer_worker;
 mod audio;
 mod cache;
+mod duplicates;
+mod errors;
+mod estimate;
+mod organize;
+mod priority;
+mod processes;
+mod reference;
+mod replaygain;
+mod results;
+mod scan_state;
 mod settings;
 mod tagging;
+mod trash;
 mod types;
+mod watcher;
 
 use num_cpus;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::prelude::*;
 use rayon::ThreadPoolBuilder;
 
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{async_runtime, Emitter, Manager};
 use walkdir::WalkDir;
 
 use audio::{analyze_with_wmb_single, analyze_file_quality, extract_metadata_from_file, is_audio, probe_bitrate, probe_duration};
-use cache::{cache_path, load_cache, save_cache};
-pub use settings::{get_settings, load_settings, save_settings};
-use types::{DownloadResult, QueueStats, RedownloadResult, ScanResult, SearchResult};
+use cache::{cache_path, load_cache, lock_cache, save_cache};
+use errors::{CommandError, ErrorCode};
+pub use settings::{get_settings, load_settings, save_settings, Settings};
+use types::{CacheEntry, DownloadResult, QueueStats, RedownloadResult, RevealFailure, ScanProgress, ScanResult, ScanStatus, SearchResult};
 
 /// Core API URL - always uses production server
 const CORE_API_URL: &str = "https://keson.api.acab.love";
@@ -41,11 +56,20 @@ async fn download_link(
     output_dir: Option<String>,
     app: tauri::AppHandle,
 ) -> Result<DownloadResult, String> {
-    let out_dir = match output_dir.filter(|s| !s.is_empty()) {
-        Some(d) => d,
-        None => app.path().download_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| String::from("./")),
+    // `~` expands to the home folder; `{artist}`/`{album}` components are filled from
+    // the downloaded file's tags once it's here
+    let (out_dir, subfolders) = match output_dir.filter(|s| !s.is_empty()) {
+        Some(d) => {
+            let expanded = organize::expand_tilde(&d, dirs::home_dir().as_deref());
+            let (base, rest) = organize::split_output_template(&expanded);
+            (base.to_string_lossy().to_string(), rest)
+        }
+        None => (
+            app.path().download_dir()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| String::from("./")),
+            None,
+        ),
     };
     let settings = load_settings(&app);
     
@@ -67,6 +91,10 @@ async fn download_link(
 
     let mut res = download_task_result?;
 
+    if let Some(template) = subfolders {
+        res.saved_to = file_into_subfolders(&res, &out_dir, &template)?;
+    }
+
     let handle = app.clone();
     let settings_analysis = settings.clone();
     
@@ -92,13 +120,14 @@ async fn download_link(
                 let analysis = analyze_with_wmb_single(
                     path,
                     &handle, // Pass AppHandle
+                    &settings_analysis,
                     settings_analysis.min_bitrate,
                     settings_analysis.analysis_window_seconds,
                     settings_analysis.cache_enabled,
                     &cache,
                 );
 
-                if let Ok((est, _lossless, note, _status)) = analysis {
+                if let Ok(audio::Analysis { bitrate: est, note, .. }) = analysis {
                     if let Some(bitrate) = est {
                         res.bitrate = Some(bitrate);
                         res.quality = format!("{} kbps", bitrate);
@@ -112,9 +141,7 @@ async fn download_link(
                 }
             }
             
-            if let Ok(guard) = cache.lock() {
-                let _ = save_cache(&cache_path, &*guard);
-            }
+            let _ = save_cache(&cache_path, &lock_cache(&cache));
         }
          Ok(res)
     }).await.map_err(|e| e.to_string())?;
@@ -123,6 +150,98 @@ async fn download_link(
 }
 
 
+/// The http(s) link in a clipboard text, which must hold nothing else around it
+fn clipboard_url(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Le presse-papiers est vide".into());
+    }
+    match reqwest::Url::parse(text) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => Ok(url.to_string()),
+        _ => Err("Le presse-papiers ne contient pas de lien http(s)".into()),
+    }
+}
+
+/// Download the link on the clipboard, like download_link with the default output folder
+#[tauri::command]
+async fn download_from_clipboard(app: tauri::AppHandle) -> Result<DownloadResult, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| format!("Lecture du presse-papiers impossible: {}", e))?;
+    let url = clipboard_url(&text)?;
+    log::info!("[GUI] Downloading clipboard link: {}", url);
+    download_link(url, None, app).await
+}
+
+/// Move a download from `out_dir` into the `{artist}/{album}` folders of `template`,
+/// returning its new path
+fn file_into_subfolders(res: &DownloadResult, out_dir: &str, template: &str) -> Result<String, String> {
+    let saved = PathBuf::from(&res.saved_to);
+    let meta = types::ExtractedMetadata {
+        artist: res.artist.clone(),
+        album: res.album.clone(),
+        title: Some(res.title.clone()),
+        ..Default::default()
+    };
+    let dir = Path::new(out_dir).join(organize::render_template(template, &meta, &res.title));
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Impossible de créer le dossier {}: {}", dir.display(), e))?;
+    let target = organize::unique_destination(&dir.join(saved.file_name().unwrap_or_default()));
+    organize::move_file(&saved, &target)?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Bytes read between two throttling pauses
+const DOWNLOAD_CHUNK_BYTES: usize = 16 * 1024;
+
+/// How long to wait after `bytes` were received in `elapsed` to stay under `limit_kbps`
+fn throttle_delay(bytes: u64, elapsed: std::time::Duration, limit_kbps: u32) -> std::time::Duration {
+    let target = std::time::Duration::from_secs_f64(bytes as f64 * 8.0 / (limit_kbps as f64 * 1000.0));
+    target.saturating_sub(elapsed)
+}
+
+/// Longest wait for a download connection to open
+const DOWNLOAD_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+/// Longest wait for the response, then for each read of its body. The blocking client
+/// restarts it on every read, so a stalled server fails while a throttled download,
+/// however long in total, never comes near it.
+const DOWNLOAD_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// HTTP client for downloads, limited by connection and idle time rather than a
+/// deadline on the whole transfer
+fn download_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .connect_timeout(DOWNLOAD_CONNECT_TIMEOUT)
+        .timeout(DOWNLOAD_IDLE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Client build failed: {e}"))
+}
+
+/// Write a downloaded body to `file`, at most `limit_kbps` kilobits per second when set
+fn save_download(
+    resp: &mut reqwest::blocking::Response,
+    file: &mut fs::File,
+    limit_kbps: Option<u32>,
+) -> std::io::Result<u64> {
+    use std::io::{Read, Write};
+    let Some(limit) = limit_kbps else {
+        return resp.copy_to(file).map_err(std::io::Error::other);
+    };
+    let started = std::time::Instant::now();
+    let mut buf = vec![0u8; DOWNLOAD_CHUNK_BYTES];
+    let mut written = 0u64;
+    loop {
+        let n = resp.read(&mut buf)?;
+        if n == 0 {
+            return Ok(written);
+        }
+        file.write_all(&buf[..n])?;
+        written += n as u64;
+        std::thread::sleep(throttle_delay(written, started.elapsed(), limit));
+    }
+}
 
 fn download_via_api(
     url: &str,
@@ -130,10 +249,8 @@ fn download_via_api(
     client_token: &str,
     app: &tauri::AppHandle,
 ) -> Result<DownloadResult, String> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(300))
-        .build()
-        .map_err(|e| format!("Client build failed: {e}"))?;
+    let rate_limit = load_settings(app).download_rate_limit();
+    let client = download_client()?;
     
     let res = client
         .post(format!("{}/download-any", CORE_API_URL))
@@ -191,7 +308,9 @@ fn download_via_api(
     fs::create_dir_all(output_dir).map_err(|e| format!("Create dir failed: {e}"))?;
     let dest_path = Path::new(output_dir).join(filename);
     let mut file = fs::File::create(&dest_path).map_err(|e| format!("Create file failed: {e}"))?;
-    dl_res.copy_to(&mut file).map_err(|e| format!("Save file failed: {e}"))?;
+    save_download(&mut dl_res, &mut file, rate_limit).map_err(|e| format!("Save file failed: {e}"))?;
+    // Closed before the analyzer and the tagger open it again
+    drop(file);
 
     let metadata = body.get("metadata");
     
@@ -237,6 +356,27 @@ fn download_via_api(
         .and_then(|s| s.as_str())
         .map(|s| s.to_string());
 
+    // Replace the source's own tags with what the Core API resolved; the filename
+    // fallback above isn't worth writing as a title
+    let resolved = types::ExtractedMetadata {
+        artist: artist.clone(),
+        title: metadata
+            .and_then(|m| m.get("title"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string()),
+        album: album.clone(),
+        ..Default::default()
+    };
+    let source_name = source.as_deref().unwrap_or("unknown");
+    let tags = match tagging::write_download_tags(&dest_path, &resolved, source_name, url) {
+        Ok(true) => Some(extract_metadata_from_file(&dest_path, app)),
+        Ok(false) => None,
+        Err(e) => {
+            log::warn!("[download] Could not tag {:?}: {}", dest_path, e);
+            None
+        }
+    };
+
     // Try to extract embedded cover from the downloaded file
     let cover_url = extract_embedded_cover(&dest_path.to_string_lossy(), app)
         .ok()
@@ -261,6 +401,7 @@ fn download_via_api(
         quality,
         warning: String::new(),
         saved_to: dest_path.to_string_lossy().to_string(),
+        tags,
     })
 }
 
@@ -373,145 +514,1087 @@ fn extract_embedded_cover(audio_path: &str, _app: &tauri::AppHandle) -> Result<O
 async fn scan_folder(
     folder: String,
     min_kbps: Option<u32>,
+    count_only: Option<bool>,
+    follow_symlinks: Option<bool>,
+    min_bytes: Option<u64>,
+    max_bytes: Option<u64>,
     app: tauri::AppHandle,
-) -> Result<Vec<ScanResult>, String> {
+) -> Result<Vec<ScanResult>, CommandError> {
     let handle = app.clone();
+    let count_only = count_only.unwrap_or(false);
     async_runtime::spawn_blocking(move || {
         let settings = load_settings(&handle);
-        init_rayon_pool_with(settings.rayon_threads);
-        let min = min_kbps.unwrap_or(settings.min_bitrate);
         let root = Path::new(&folder);
         if !root.exists() {
-            return Err("Dossier introuvable".into());
+            return Err(CommandError::new(ErrorCode::FolderNotFound));
         }
 
-        let mut audio_entries = Vec::new();
-        let mut discovered = 0usize;
-        let mut tick = 0u32;
-        let _ = handle.emit("scan_progress", 1u32);
+        let scope = ScanScope::new(follow_symlinks, min_bytes, max_bytes)?;
+        let tracker = handle.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker)?;
+        let found = discover_audio(&handle, &settings, root, &scope)?;
+
+        if found.entries.is_empty() {
+            let _ = handle.emit("scan_progress", 100u32);
+            return Ok(found.skipped);
+        }
 
-        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-            if entry.file_type().is_dir() && entry.file_name() == "backup-ksi" {
+        // Dry run: list the candidates without hashing or invoking the analyzer
+        if count_only {
+            let mut pending: Vec<ScanResult> = found
+                .entries
+                .iter()
+                .map(|(path, size)| ScanResult {
+                    path: path.display().to_string(),
+                    name: file_name_of(path),
+                    status: ScanStatus::Pending,
+                    size_bytes: Some(*size),
+                    taggable: tagging::supports_tagging(path),
+                    ..Default::default()
+                })
+                .collect();
+            log::info!("[scan] Dry run found {} candidate files", pending.len());
+            pending.extend(found.skipped);
+            let _ = handle.emit("scan_progress", 100u32);
+            return Ok(pending);
+        }
+
+        run_scan(&handle, &settings, root, &found, min_kbps)
+    })
+    .await
+    .map_err(|e| CommandError::from(e.to_string()))?
+}
+
+/// Analyze a folder like scan_folder but return only the aggregate figures. The
+/// rows still become the last scan, so query_results can page through them.
+#[tauri::command]
+async fn scan_summary(
+    folder: String,
+    min_kbps: Option<u32>,
+    follow_symlinks: Option<bool>,
+    min_bytes: Option<u64>,
+    max_bytes: Option<u64>,
+    app: tauri::AppHandle,
+) -> Result<results::ScanSummary, CommandError> {
+    async_runtime::spawn_blocking(move || {
+        let settings = load_settings(&app);
+        let root = Path::new(&folder);
+        if !root.exists() {
+            return Err(CommandError::new(ErrorCode::FolderNotFound));
+        }
+
+        let scope = ScanScope::new(follow_symlinks, min_bytes, max_bytes)?;
+        let tracker = app.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker)?;
+        let found = discover_audio(&app, &settings, root, &scope)?;
+        if found.entries.is_empty() {
+            let _ = app.emit("scan_progress", 100u32);
+            return Ok(results::ScanSummary {
+                skipped_by_size: found.skipped_by_size,
+                ..results::summarize(&found.skipped)
+            });
+        }
+        let results = run_scan(&app, &settings, root, &found, min_kbps)?;
+        Ok(results::ScanSummary {
+            skipped_by_size: found.skipped_by_size,
+            ..results::summarize(&results)
+        })
+    })
+    .await
+    .map_err(|e| CommandError::from(e.to_string()))?
+}
+
+/// Estimate how long scanning `folder` would take: the discovery pass counts its files,
+/// and the rolling per-file times of past scans price them. Files the folder's last
+/// scan analyzed, unchanged in size, are expected to hit the cache. Nothing is hashed
+/// or analyzed.
+#[tauri::command]
+async fn estimate_scan(
+    folder: String,
+    follow_symlinks: Option<bool>,
+    min_bytes: Option<u64>,
+    max_bytes: Option<u64>,
+    app: tauri::AppHandle,
+) -> Result<estimate::ScanEstimate, CommandError> {
+    async_runtime::spawn_blocking(move || {
+        let settings = load_settings(&app);
+        let root = Path::new(&folder);
+        if !root.exists() {
+            return Err(CommandError::new(ErrorCode::FolderNotFound));
+        }
+
+        let scope = ScanScope::new(follow_symlinks, min_bytes, max_bytes)?;
+        let tracker = app.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker)?;
+        let found = discover_audio(&app, &settings, root, &scope)?;
+        let previous = if settings.cache_enabled {
+            results::last_scan_of(&app, root)
+                .map(|scan| scan.results)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let _ = app.emit("scan_progress", 100u32);
+        Ok(estimate::estimate_scan_of(
+            &estimate::load_timing(&app),
+            &found.entries,
+            &previous,
+            rayon::current_num_threads(),
+        ))
+    })
+    .await
+    .map_err(|e| CommandError::from(e.to_string()))?
+}
+
+/// Claim the scan slot for the calling command, refusing to overlap another scan
+fn start_scan(tracker: &scan_state::ScanTracker) -> Result<scan_state::RunningScan<'_>, CommandError> {
+    tracker
+        .try_start()
+        .ok_or_else(|| CommandError::new(ErrorCode::ScanInProgress))
+}
+
+/// Audio files found under a scanned folder
+struct Discovered {
+    /// (path, size) of each file to analyze
+    entries: Vec<(PathBuf, u64)>,
+    bytes_total: u64,
+    /// Rows for files left out up front (too small to be real tracks)
+    skipped: Vec<ScanResult>,
+    /// Audio files outside the scan's size band, left out without a row
+    skipped_by_size: usize,
+}
+
+/// Which files a folder scan considers, beyond the extension and hidden-file settings
+#[derive(Default)]
+struct ScanScope {
+    follow_symlinks: bool,
+    min_bytes: Option<u64>,
+    max_bytes: Option<u64>,
+}
+
+impl ScanScope {
+    fn new(
+        follow_symlinks: Option<bool>,
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> Result<Self, CommandError> {
+        if let (Some(min), Some(max)) = (min_bytes, max_bytes) {
+            if min > max {
+                return Err(CommandError::with_message(
+                    ErrorCode::InvalidArgument,
+                    format!("Taille minimale ({} o) supérieure à la maximale ({} o)", min, max),
+                ));
+            }
+        }
+        Ok(Self {
+            follow_symlinks: follow_symlinks.unwrap_or(false),
+            min_bytes,
+            max_bytes,
+        })
+    }
+
+    fn size_in_band(&self, size: u64) -> bool {
+        self.min_bytes.is_none_or(|min| size >= min) && self.max_bytes.is_none_or(|max| size <= max)
+    }
+}
+
+/// Walk `root`, skipping hidden entries below it when `skip_hidden` is set. With
+/// `follow_symlinks`, linked directories are walked as if they were real ones: a link
+/// back to one of its own ancestors yields a loop error instead of recursing, and a
+/// folder linked twice is walked twice.
+fn walk_library(
+    root: &Path,
+    skip_hidden: bool,
+    follow_symlinks: bool,
+) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> {
+    WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(move |e| !(skip_hidden && e.depth() > 0 && audio::is_hidden(e)))
+}
+
+/// Walk `root` for audio files (skipping backup-ksi, and hidden files unless
+/// `skip_hidden` is off), emitting discovery progress. Directory symlinks are only
+/// entered with `follow_symlinks`; files outside the size band are only counted.
+fn discover_audio(
+    handle: &tauri::AppHandle,
+    settings: &Settings,
+    root: &Path,
+    scope: &ScanScope,
+) -> Result<Discovered, CommandError> {
+    let mut found = Discovered {
+        entries: Vec::new(),
+        bytes_total: 0,
+        skipped: Vec::new(),
+        skipped_by_size: 0,
+    };
+    let mut discovered = 0usize;
+    let mut tick = 0u32;
+    let mut walk_errors = 0usize;
+    let mut unreadable = 0usize;
+    let _ = handle.emit("scan_progress", 1u32);
+    let tracker = handle.state::<scan_state::ScanTracker>();
+    let _phase = tracker.begin(scan_state::ScanPhase::Discovering, 0);
+
+    let extensions = settings.scan_extensions();
+    for entry in walk_library(root, settings.skip_hidden, scope.follow_symlinks) {
+        // Whatever was found so far comes back as "cancelled" rows
+        if audio::SCAN_CANCELLED.load(Ordering::SeqCst) {
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => {
+                walk_errors = 0;
+                entry
+            }
+            // A symlink loop is a property of the library, not a sign the folder went away
+            Err(e) if e.loop_ancestor().is_some() => {
+                log::warn!("[scan] Skipping symlink loop: {}", e);
                 continue;
             }
-            if entry.file_type().is_file() {
-                if entry.path().components().any(|c| c.as_os_str() == "backup-ksi") {
-                    continue;
-                }
-                discovered += 1;
-                if is_audio(entry.path()) {
-                    audio_entries.push(entry);
+            // walkdir doesn't descend into a folder it can't read, so the walk carries
+            // on with its siblings. A run of errors only ends the scan when the root
+            // itself is gone (unmounted volume), not for a few locked-down folders.
+            Err(e) => {
+                walk_errors += 1;
+                unreadable += 1;
+                log::warn!("[scan] Cannot read entry: {}", e);
+                if walk_errors >= MOUNT_LOSS_THRESHOLD {
+                    if fs::read_dir(root).is_err() {
+                        return Err(CommandError::with_message(
+                            ErrorCode::FolderUnreachable,
+                            scan_failed(handle, root),
+                        ));
+                    }
+                    walk_errors = 0;
                 }
-                let pct = 1 + ((discovered as f64).sqrt() as u32 % 12);
-                if pct != tick {
-                    tick = pct;
-                    let _ = handle.emit("scan_progress", pct.min(15));
+                continue;
+            }
+        };
+        if entry.file_type().is_dir() && entry.file_name() == "backup-ksi" {
+            continue;
+        }
+        if entry.file_type().is_file() {
+            if entry.path().components().any(|c| c.as_os_str() == "backup-ksi") {
+                continue;
+            }
+            discovered += 1;
+            tracker.set_done(discovered);
+            if is_audio(entry.path(), &extensions) {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                if !scope.size_in_band(size) {
+                    found.skipped_by_size += 1;
+                } else if size < audio::MIN_AUDIO_FILE_BYTES {
+                    found.skipped.push(ScanResult {
+                        path: entry.path().display().to_string(),
+                        name: file_name_of(entry.path()),
+                        note: Some(format!("Fichier trop petit pour être un morceau ({} octets)", size)),
+                        status: ScanStatus::Skipped,
+                        size_bytes: Some(size),
+                        ..Default::default()
+                    });
+                } else {
+                    found.bytes_total += size;
+                    found.entries.push((entry.into_path(), size));
                 }
             }
+            let pct = 1 + ((discovered as f64).sqrt() as u32 % 12);
+            if pct != tick {
+                tick = pct;
+                let _ = handle.emit("scan_progress", pct.min(15));
+            }
         }
+    }
+    if !found.skipped.is_empty() {
+        log::info!("[scan] Skipped {} files too small to be tracks", found.skipped.len());
+    }
+    if found.skipped_by_size > 0 {
+        log::info!("[scan] Left out {} files outside the size band", found.skipped_by_size);
+    }
+    if unreadable > 0 {
+        log::warn!("[scan] {} entries under {:?} could not be read", unreadable, root);
+        let _ = handle.emit(
+            "scan_warning",
+            types::ScanWarning::new(
+                types::ScanWarningCode::UnreadableEntries,
+                format!(
+                    "{} dossiers ou fichiers illisibles ont été ignorés (droits d'accès ?)",
+                    unreadable
+                ),
+            ),
+        );
+    }
+    let audio_files = found.entries.len() + found.skipped.len() + found.skipped_by_size;
+    if root_looks_too_broad(discovered, audio_files) {
+        log::warn!(
+            "[scan] {:?} holds {} files for {} audio files, root is probably too broad",
+            root, discovered, audio_files
+        );
+        let _ = handle.emit(
+            "scan_warning",
+            types::ScanWarning::new(
+                types::ScanWarningCode::BroadRoot,
+                format!(
+                    "{} fichiers parcourus pour {} fichiers audio : le dossier choisi est peut-être trop large",
+                    discovered, audio_files
+                ),
+            ),
+        );
+    }
+    Ok(found)
+}
 
-        if audio_entries.is_empty() {
-            let _ = handle.emit("scan_progress", 100u32);
-            return Ok(Vec::new());
-        }
+/// Files walked before a root can be flagged as too broad
+const BROAD_ROOT_MIN_FILES: usize = 100_000;
+/// Walked files per audio file above which a root is flagged as too broad
+const BROAD_ROOT_RATIO: usize = 50;
 
-        let cache_path = cache_path(&handle)?;
-        let cache = Arc::new(Mutex::new(load_cache(
-            &cache_path,
-            settings.cache_max_entries,
-        )));
-        let total = audio_entries.len();
-        let counter = AtomicUsize::new(0);
+/// Whether a walk of `walked` files that found `audio` audio files points at a folder
+/// that isn't a music library (a home or system folder)
+fn root_looks_too_broad(walked: usize, audio: usize) -> bool {
+    walked > BROAD_ROOT_MIN_FILES && walked > audio.saturating_mul(BROAD_ROOT_RATIO)
+}
 
-        let results: Vec<ScanResult> = audio_entries
-            .par_iter()
-            .map(|entry| {
-                let path = entry.path();
-                let analysis = analyze_with_wmb_single(
-                    path,
-                    &handle, // Pass AppHandle
-                    min,
-                    settings.analysis_window_seconds,
-                    settings.cache_enabled,
-                    &cache,
-                );
-                let (bitrate, is_lossless, note, status) = match analysis {
-                    Ok(res) => res,
-                    Err(err) => {
-                        log::error!("[scan] Analysis FAILED for {:?}: {}", path, err);
-                        (None, None, Some(err), "error".to_string())
-                    }
-                };
+/// Analyze discovered files, then persist them and make them the last scan (unless the
+/// scan was cancelled)
+fn run_scan(
+    handle: &tauri::AppHandle,
+    settings: &Settings,
+    root: &Path,
+    found: &Discovered,
+    min: Option<u32>,
+) -> Result<Vec<ScanResult>, CommandError> {
+    handle.state::<results::ResultsState>().clear();
+    let Some(mut results) =
+        analyze_batch(handle, settings, &found.entries, found.bytes_total, min, None)?
+    else {
+        return Err(CommandError::with_message(
+            ErrorCode::FolderUnreachable,
+            scan_failed(handle, root),
+        ));
+    };
+    results.extend(found.skipped.iter().cloned());
 
-                // Check if file has been replaced (has KESON_REPLACED tag)
-                let replaced = tagging::has_replaced_tag(path);
-                
-                // If file was replaced, mark status as "replaced" instead of "bad"
-                let final_status = if replaced && status == "bad" {
-                    "replaced".to_string()
+    // A cancelled run is mostly "cancelled" rows: it mustn't become the last scan or
+    // push a complete one out of results.json
+    if audio::SCAN_CANCELLED.load(Ordering::SeqCst) {
+        return Ok(results);
+    }
+    results::record_scan(handle, root, &results);
+    handle
+        .state::<results::ResultsState>()
+        .replace(results.clone());
+    Ok(results)
+}
+
+/// Files needing the analyzer that must all fail for lack of one before a scan gives up
+const ANALYZER_UNAVAILABLE_ABORT_AFTER: usize = 5;
+
+/// Minimum time between two "scan_telemetry" events
+const SCAN_TELEMETRY_INTERVAL_MS: u64 = 1000;
+
+/// Whether a telemetry event is due at `now_ms` (since the scan started); claims the
+/// slot so only one of the threads finishing a file around then emits it
+fn telemetry_due(last_ms: &AtomicU64, now_ms: u64) -> bool {
+    let last = last_ms.load(Ordering::SeqCst);
+    now_ms >= last + SCAN_TELEMETRY_INTERVAL_MS
+        && last_ms
+            .compare_exchange(last, now_ms, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+}
+
+/// Mid-scan cache saves, whichever threshold is reached first
+const CACHE_FLUSH_EVERY_FILES: usize = 500;
+const CACHE_FLUSH_EVERY_SECS: u64 = 30;
+
+/// Analyze a batch of (path, size) files in parallel through the caching pipeline,
+/// emitting progress and saving the cache at the end. `min` and `window` are the
+/// caller's explicit thresholds; where None, folder overrides then the settings apply
+/// per file. Returns None when the scan was aborted because files became unreachable,
+/// and an error when the first files sent to the analyzer all failed because it isn't
+/// installed.
+fn analyze_batch(
+    handle: &tauri::AppHandle,
+    settings: &Settings,
+    files: &[(PathBuf, u64)],
+    bytes_total: u64,
+    min: Option<u32>,
+    window: Option<u32>,
+) -> Result<Option<Vec<ScanResult>>, CommandError> {
+    let cache_path = cache_path(handle).map_err(CommandError::from)?;
+    let cache = Arc::new(Mutex::new(load_cache(
+        &cache_path,
+        settings.cache_max_entries,
+    )));
+    let active = handle.state::<cache::ActiveCaches>();
+    active.register(&cache_path, &cache);
+    let total = files.len();
+    let counter = AtomicUsize::new(0);
+    let bytes_done = AtomicU64::new(0);
+    let io_errors = AtomicUsize::new(0);
+    let aborted = std::sync::atomic::AtomicBool::new(false);
+    // Analyzer-unavailable failures before any fresh analysis succeeded
+    let unavailable = AtomicUsize::new(0);
+    let analyzer_worked = std::sync::atomic::AtomicBool::new(false);
+    let analyzer_down: Mutex<Option<types::ScanWarning>> = Mutex::new(None);
+    let cache_hits = AtomicUsize::new(0);
+    let cache_misses = AtomicUsize::new(0);
+    let telemetry_at = AtomicU64::new(0);
+    let analyzer_slots = audio::analyzer_slot_limit(settings.max_concurrent_analyses);
+    let flush = cache::PeriodicFlush::new(
+        CACHE_FLUSH_EVERY_FILES,
+        std::time::Duration::from_secs(CACHE_FLUSH_EVERY_SECS),
+    );
+    let started = std::time::Instant::now();
+    let telemetry = |done: usize| types::ScanTelemetry {
+        files_done: done,
+        files_per_sec: done as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON),
+        cache_hits: cache_hits.load(Ordering::SeqCst),
+        cache_misses: cache_misses.load(Ordering::SeqCst),
+        active_analyzers: audio::active_analyzers(),
+        analyzer_slots,
+    };
+    let low_priority = settings.scan_priority == "low";
+    let _priority = low_priority.then(priority::LowPriorityScope::enter);
+    // (analyzed, cached) thread-busy times, for estimate_scan
+    let timings = Mutex::new((estimate::TimingSample::default(), estimate::TimingSample::default()));
+    let tracker = handle.state::<scan_state::ScanTracker>();
+    let _phase = tracker.begin(scan_state::ScanPhase::Analyzing, total);
+
+    let results: Vec<ScanResult> = files
+        .par_iter()
+        .map(|(path, size)| {
+            if aborted.load(Ordering::SeqCst) {
+                return None;
+            }
+            if low_priority {
+                priority::lower_current_thread();
+            }
+            let (min, window) = settings.thresholds_for(path, min, window);
+            // After cancel_scan, the files left are listed without being analyzed
+            let cancelled = audio::SCAN_CANCELLED.load(Ordering::SeqCst);
+            // The tag check is far cheaper than hashing and analyzing the file
+            let skip = cancelled || (settings.skip_replaced && tagging::has_replaced_tag(path));
+            let file_started = std::time::Instant::now();
+            tracker.file_started(path);
+            let result = if cancelled {
+                cancelled_row(path, *size)
+            } else if skip {
+                replaced_row(path, *size)
+            } else {
+                scan_file(path, Some(*size), handle, min, window, settings, &cache)
+            };
+            tracker.file_finished();
+            if result.cached {
+                cache_hits.fetch_add(1, Ordering::SeqCst);
+            } else if result.cache_missed {
+                cache_misses.fetch_add(1, Ordering::SeqCst);
+            }
+            if !skip && result.status.is_analyzed() {
+                let secs = file_started.elapsed().as_secs_f64();
+                let mut timings = timings.lock().unwrap_or_else(|e| e.into_inner());
+                if result.cached {
+                    timings.1.add(secs);
                 } else {
-                    status
-                };
+                    timings.0.add(secs);
+                }
+            }
 
-                let done = counter.fetch_add(1, Ordering::SeqCst) + 1;
-                let percent: f64 = 15.0 + (done as f64 / total as f64) * 85.0;
-                let _ = handle.emit("scan_progress", percent.round() as u32);
+            let unavailable_error = result
+                .warnings
+                .iter()
+                .find(|w| audio::analyzer_unavailable(w));
+            match unavailable_error {
+                Some(w) if !analyzer_worked.load(Ordering::SeqCst) => {
+                    let failed = unavailable.fetch_add(1, Ordering::SeqCst) + 1;
+                    if failed >= ANALYZER_UNAVAILABLE_ABORT_AFTER {
+                        *analyzer_down.lock().unwrap_or_else(|e| e.into_inner()) = Some(w.clone());
+                        aborted.store(true, Ordering::SeqCst);
+                        return None;
+                    }
+                }
+                Some(_) => {}
+                None if !skip && !result.cached && result.status.is_analyzed() => {
+                    analyzer_worked.store(true, Ordering::SeqCst);
+                }
+                None => {}
+            }
 
-                ScanResult {
-                    path: path.display().to_string(),
-                    name: entry.file_name().to_string_lossy().into(),
-                    bitrate,
-                    is_lossless,
-                    note,
-                    status: final_status,
-                    replaced,
+            // A file that errored and can no longer be stat'ed points at the volume
+            // going away rather than a bad file
+            if result.status == ScanStatus::Error && fs::metadata(path).is_err() {
+                let streak = io_errors.fetch_add(1, Ordering::SeqCst) + 1;
+                if streak >= MOUNT_LOSS_THRESHOLD {
+                    aborted.store(true, Ordering::SeqCst);
+                    return None;
                 }
+            } else {
+                io_errors.store(0, Ordering::SeqCst);
+            }
+
+            if settings.cache_enabled {
+                flush.file_done(&cache_path, &cache);
+            }
+
+            let done = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            tracker.set_done(done);
+            let done_bytes = bytes_done.fetch_add(*size, Ordering::SeqCst) + *size;
+            let percent: f64 = 15.0 + (done as f64 / total as f64) * 85.0;
+            let _ = handle.emit("scan_progress", percent.round() as u32);
+            let _ = handle.emit(
+                "scan_progress_detail",
+                scan_progress_detail(
+                    percent.round() as u32,
+                    (done, total),
+                    (done_bytes, bytes_total),
+                    started.elapsed().as_secs_f64(),
+                ),
+            );
+            if telemetry_due(&telemetry_at, started.elapsed().as_millis() as u64) {
+                let _ = handle.emit("scan_telemetry", telemetry(done));
+            }
+
+            if low_priority && !cancelled {
+                std::thread::sleep(priority::LOW_PRIORITY_PAUSE);
+            }
+            Some(result)
+        })
+        .while_some()
+        .collect();
+    let _ = handle.emit("scan_telemetry", telemetry(counter.load(Ordering::SeqCst)));
+
+    let _ = save_cache(&cache_path, &lock_cache(&cache));
+    active.unregister(&cache);
+
+    if let Some(warning) = analyzer_down.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        log::error!(
+            "[scan] Aborting: the first {} analyses failed without an analyzer: {}",
+            ANALYZER_UNAVAILABLE_ABORT_AFTER, warning.message
+        );
+        let code = if warning.code == types::ScanWarningCode::AnalyzerDepsMissing {
+            ErrorCode::PythonDepsMissing
+        } else {
+            ErrorCode::AnalyzerNotFound
+        };
+        return Err(CommandError::with_message(
+            code,
+            format!("{}. Scan interrompu : {}", code.default_message(), warning.message),
+        ));
+    }
+    if aborted.load(Ordering::SeqCst) {
+        return Ok(None);
+    }
+    if audio::SCAN_CANCELLED.load(Ordering::SeqCst) {
+        let _ = handle.emit("scan_progress", 100u32);
+    } else {
+        let (analyzed, cached) = timings.into_inner().unwrap_or_else(|e| e.into_inner());
+        estimate::record_timing(handle, &analyzed, &cached);
+    }
+    Ok(Some(results))
+}
+
+/// Row for a file the scan didn't get to before it was cancelled
+fn cancelled_row(path: &Path, size: u64) -> ScanResult {
+    ScanResult {
+        path: path.display().to_string(),
+        name: file_name_of(path),
+        note: Some("Scan annulé avant l'analyse".to_string()),
+        status: ScanStatus::Cancelled,
+        size_bytes: Some(size),
+        taggable: tagging::supports_tagging(path),
+        ..Default::default()
+    }
+}
+
+/// Row for a KESON_REPLACED file left unanalyzed because of `skip_replaced`
+fn replaced_row(path: &Path, size: u64) -> ScanResult {
+    ScanResult {
+        path: path.display().to_string(),
+        name: file_name_of(path),
+        note: Some("Déjà remplacé, non analysé".to_string()),
+        status: ScanStatus::Replaced,
+        replaced: true,
+        size_bytes: Some(size),
+        taggable: tagging::supports_tagging(path),
+        ..Default::default()
+    }
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into())
+        .unwrap_or_default()
+}
+
+/// Analyze one file again even if it is cached, replacing its cache entry with the
+/// fresh result (e.g. after an analyzer fix, or an edit the hash check missed)
+#[tauri::command]
+async fn reanalyze_file(path: String, app: tauri::AppHandle) -> Result<ScanResult, String> {
+    async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(&path);
+        let meta = fs::metadata(&path).map_err(|_| "Fichier introuvable".to_string())?;
+        if !meta.is_file() {
+            return Err("Pas un fichier".into());
+        }
+        // A scan or folder watch holding the cache in memory would save its own copy
+        // over the fresh entry when it finishes
+        let tracker = app.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker).map_err(|e| e.message)?;
+        let active = app.state::<cache::ActiveCaches>();
+        if active.is_busy() {
+            return Err("Une analyse est en cours, réessayez après".into());
+        }
+        let settings = load_settings(&app);
+        let cache_path = cache_path(&app)?;
+        let cache = Arc::new(Mutex::new(load_cache(&cache_path, settings.cache_max_entries)));
+        active.register(&cache_path, &cache);
+
+        // Dropping the entry turns the lookup into a miss, and the fresh analysis is
+        // then stored under the same hash
+        if let Ok(hash) = audio::file_hash(&path) {
+            if lock_cache(&cache).remove(&hash).is_some() {
+                log::info!("[cache] Dropped cached analysis for {:?}", path);
+            }
+        }
+
+        let (min, window) = settings.thresholds_for(&path, None, None);
+        let result = scan_file(&path, Some(meta.len()), &app, min, window, &settings, &cache);
+        let _ = save_cache(&cache_path, &lock_cache(&cache));
+        active.unregister(&cache);
+        app.state::<results::ResultsState>().update(result.clone());
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Analyze again the rows of the last scan that ended in "error" and merge the fresh
+/// rows back into it, emitting each one as "scan_result". Failed analyses are never
+/// cached, so these files always go through the analyzer again.
+#[tauri::command]
+async fn retry_errors(min_kbps: Option<u32>, app: tauri::AppHandle) -> Result<Vec<ScanResult>, String> {
+    async_runtime::spawn_blocking(move || {
+        let state = app.state::<results::ResultsState>();
+        let paths = state.paths_with_status(ScanStatus::Error);
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tracker = app.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker).map_err(|e| e.message)?;
+        let settings = load_settings(&app);
+        let files: Vec<(PathBuf, u64)> = paths
+            .iter()
+            .map(|p| {
+                let size = fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+                (PathBuf::from(p), size)
             })
             .collect();
+        let bytes_total = files.iter().map(|(_, size)| size).sum();
+        log::info!("[scan] Retrying {} files that errored", files.len());
+
+        let _ = app.emit("scan_progress", 1u32);
+        let Some(results) = analyze_batch(
+            &app,
+            &settings,
+            &files,
+            bytes_total,
+            min_kbps,
+            None,
+        )
+        .map_err(|e| e.message)?
+        else {
+            let location = files[0].0.parent().unwrap_or(&files[0].0).to_path_buf();
+            return Err(scan_failed(&app, &location));
+        };
+        let _ = app.emit("scan_progress", 100u32);
+
+        for result in &results {
+            // Files the cancelled retry didn't reach stay errors, so they can be retried
+            if result.status != ScanStatus::Cancelled {
+                state.update(result.clone());
+            }
+            let _ = app.emit("scan_result", result);
+        }
+        Ok(results)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-        if let Ok(cache_guard) = cache.lock() {
-            let _ = save_cache(&cache_path, &*cache_guard);
+/// Analyze an explicit list of files (e.g. dropped onto the window) with the same
+/// pipeline and events as scan_folder. Paths that are missing or not audio come back
+/// as "skipped" rows with a note instead of failing the batch.
+#[tauri::command]
+async fn analyze_files(
+    paths: Vec<String>,
+    min_kbps: Option<u32>,
+    window: Option<u32>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ScanResult>, String> {
+    let handle = app.clone();
+    async_runtime::spawn_blocking(move || {
+        let tracker = handle.state::<scan_state::ScanTracker>();
+        let _running = start_scan(&tracker).map_err(|e| e.message)?;
+        let settings = load_settings(&handle);
+        let _ = handle.emit("scan_progress", 1u32);
+
+        let extensions = settings.scan_extensions();
+        let mut files = Vec::new();
+        let mut skipped = HashMap::new();
+        let mut bytes_total = 0u64;
+        for path in paths.iter().map(PathBuf::from) {
+            let note = match fs::metadata(&path) {
+                Err(_) => Some("Fichier introuvable"),
+                Ok(meta) if !meta.is_file() => Some("Pas un fichier"),
+                Ok(_) if !is_audio(&path, &extensions) => {
+                    Some("Format audio non pris en charge")
+                }
+                Ok(meta) => {
+                    bytes_total += meta.len();
+                    files.push((path.clone(), meta.len()));
+                    None
+                }
+            };
+            if let Some(note) = note {
+                skipped.insert(
+                    path.clone(),
+                    ScanResult {
+                        path: path.display().to_string(),
+                        name: file_name_of(&path),
+                        note: Some(note.to_string()),
+                        status: ScanStatus::Skipped,
+                        ..Default::default()
+                    },
+                );
+            }
         }
 
+        handle.state::<results::ResultsState>().clear();
+        let analyzed = if files.is_empty() {
+            Vec::new()
+        } else {
+            let Some(results) =
+                analyze_batch(&handle, &settings, &files, bytes_total, min_kbps, window)
+                    .map_err(|e| e.message)?
+            else {
+                let location = files[0].0.parent().unwrap_or(&files[0].0).to_path_buf();
+                return Err(scan_failed(&handle, &location));
+            };
+            results
+        };
+        let _ = handle.emit("scan_progress", 100u32);
+
+        // Keep the caller's order, interleaving skipped rows with analyzed ones
+        let mut analyzed = analyzed.into_iter();
+        let results: Vec<ScanResult> = paths
+            .iter()
+            .map(PathBuf::from)
+            .filter_map(|p| skipped.get(&p).cloned().or_else(|| analyzed.next()))
+            .collect();
+
+        handle
+            .state::<results::ResultsState>()
+            .replace(results.clone());
         Ok(results)
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// Consecutive unreadable files after which the scanned volume is suspected lost
+const MOUNT_LOSS_THRESHOLD: usize = 20;
+
+/// Emit `scan_failed` for a folder that became unreachable and build the scan error
+fn scan_failed(app: &tauri::AppHandle, root: &Path) -> String {
+    let message = format!(
+        "Le dossier {} n'est plus accessible (lecteur réseau déconnecté ?). Scan interrompu.",
+        root.display()
+    );
+    log::error!("[scan] {}", message);
+    let _ = app.emit("scan_failed", message.clone());
+    message
+}
+
+/// Analyze one file through the caching pipeline and build its scan row
+pub(crate) fn scan_file(
+    path: &Path,
+    size: Option<u64>,
+    app: &tauri::AppHandle,
+    min: u32,
+    window: u32,
+    settings: &Settings,
+    cache: &Arc<Mutex<HashMap<String, CacheEntry>>>,
+) -> ScanResult {
+    let analysis = analyze_with_wmb_single(
+        path,
+        app,
+        settings,
+        min,
+        window,
+        settings.cache_enabled,
+        cache,
+    );
+    let audio::Analysis { bitrate, is_lossless, note, status, stream, warnings, cached, cache_missed, hash, clipping, .. } = match analysis {
+        Ok(res) => res,
+        Err(err) => {
+            log::error!("[scan] Analysis FAILED for {:?}: {}", path, err.message);
+            let warning = audio::analysis_failure_warning(&err);
+            audio::Analysis {
+                // Hashing stops mid-file on cancel_scan
+                status: if warning.code == types::ScanWarningCode::Cancelled {
+                    ScanStatus::Cancelled
+                } else {
+                    ScanStatus::Error
+                },
+                warnings: vec![warning],
+                note: Some(err.message),
+                stream: err.stream,
+                cache_missed: err.cache_missed,
+                ..Default::default()
+            }
+        }
+    };
+
+    let mut note = note;
+    if clipping == Some(true) {
+        let clipped = "Crête à 0 dBFS : écrêtage probable".to_string();
+        note = Some(match note {
+            Some(n) => format!("{} | {}", n, clipped),
+            None => clipped,
+        });
+    }
+
+    // KESON_REPLACED and ReplayGain, from a single read of the tags
+    let tagging::ScanTags { replaced, replaygain_track_gain, replaygain_album_gain } =
+        tagging::read_scan_tags(path);
+
+    // If file was replaced, mark status as "replaced" instead of "bad"
+    let final_status = if replaced && status == ScanStatus::Bad {
+        ScanStatus::Replaced
+    } else {
+        status
+    };
+
+    ScanResult {
+        path: path.display().to_string(),
+        name: file_name_of(path),
+        bitrate,
+        is_lossless,
+        note,
+        status: final_status,
+        replaced,
+        size_bytes: size,
+        codec: stream.codec,
+        sample_rate: stream.sample_rate,
+        channels: stream.channels,
+        container_bitrate: stream.container_bitrate,
+        bit_depth: stream.bit_depth,
+        clipping,
+        taggable: tagging::supports_tagging(path),
+        warnings,
+        cached,
+        cache_missed,
+        replaygain_track_gain,
+        replaygain_album_gain,
+        hash,
+    }
+}
+
+/// Build a detailed progress payload, deriving the ETA from byte throughput so far
+/// (falls back to file throughput when sizes are unknown)
+fn scan_progress_detail(
+    percent: u32,
+    (files_done, files_total): (usize, usize),
+    (bytes_done, bytes_total): (u64, u64),
+    elapsed_secs: f64,
+) -> ScanProgress {
+    let eta_secs = if elapsed_secs <= 0.0 {
+        None
+    } else if bytes_done > 0 && bytes_total >= bytes_done {
+        let rate = bytes_done as f64 / elapsed_secs;
+        Some((bytes_total - bytes_done) as f64 / rate)
+    } else if files_done > 0 {
+        let rate = files_done as f64 / elapsed_secs;
+        Some(files_total.saturating_sub(files_done) as f64 / rate)
+    } else {
+        None
+    };
+
+    ScanProgress {
+        percent,
+        files_done,
+        files_total,
+        bytes_done,
+        bytes_total,
+        elapsed_secs,
+        eta_secs,
+    }
+}
+
 #[tauri::command]
-async fn reveal_in_folder(path: String) -> Result<(), String> {
+async fn reveal_in_folder(path: String) -> Result<(), CommandError> {
     if !Path::new(&path).exists() {
-        return Err("Fichier introuvable".into());
+        return Err(CommandError::new(ErrorCode::FileNotFound));
     }
+    let open_failed = |e: String| CommandError::with_message(ErrorCode::OpenFailed, e);
     #[cfg(target_os = "macos")]
     {
-        Command::new("open")
+        let status = Command::new("open")
             .arg("-R")
             .arg(&path)
             .status()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| open_failed(e.to_string()))?;
+        if !status.success() {
+            return Err(open_failed(format!("open -R a échoué ({})", status)));
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        let win_path = to_windows_path(&path);
+        let open_parent = || -> Result<(), CommandError> {
+            let dir = Path::new(&win_path)
+                .parent()
+                .ok_or_else(|| open_failed("Chemin sans dossier".into()))?;
+            open_directory(dir).map_err(open_failed)
+        };
+        // explorer's exit code is unreliable (1 on success) and it opens a default
+        // folder instead of failing when the file to select is gone, so the path is
+        // checked up front
+        if !Path::new(&win_path).exists() {
+            log::warn!("[reveal] {} no longer exists, opening parent folder instead", win_path);
+            open_parent()?;
+        } else {
+            // explorer parses its own command line: `/select,"PATH"` must reach it as
+            // one unescaped argument, so bypass std's quoting with raw_arg
+            let selected = Command::new("explorer")
+                .raw_arg(explorer_select_arg(&win_path))
+                .status();
+            if let Err(e) = selected {
+                log::warn!("[reveal] explorer /select failed ({}), opening parent folder instead", e);
+                open_parent()?;
+            }
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(dir) = Path::new(&path).parent() {
+            open_directory(dir).map_err(open_failed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Open the folder containing `path` without selecting the file
+#[tauri::command]
+async fn open_containing_folder(path: String) -> Result<(), String> {
+    let p = Path::new(&path);
+    if !p.exists() {
+        return Err("Fichier introuvable".into());
+    }
+    let dir = if p.is_dir() {
+        p
+    } else {
+        p.parent().ok_or("Chemin sans dossier")?
+    };
+    open_directory(dir)
+}
+
+/// Maximum number of folders reveal_many will open in one call
+const REVEAL_MANY_LIMIT: usize = 20;
+
+/// Open the folders of several results at once, once per unique parent directory.
+/// Returns the folders that failed to open.
+#[tauri::command]
+async fn reveal_many(paths: Vec<String>) -> Result<Vec<RevealFailure>, String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut dirs = Vec::new();
+    let mut failures = Vec::new();
+
+    for path in &paths {
+        let p = Path::new(path);
+        if !p.exists() {
+            failures.push(RevealFailure {
+                path: path.clone(),
+                error: "Fichier introuvable".into(),
+            });
+            continue;
+        }
+        let dir = if p.is_dir() { Some(p) } else { p.parent() };
+        match dir {
+            Some(d) => {
+                if seen.insert(d.to_path_buf()) {
+                    dirs.push(d.to_path_buf());
+                }
+            }
+            None => failures.push(RevealFailure {
+                path: path.clone(),
+                error: "Chemin sans dossier".into(),
+            }),
+        }
+    }
+
+    if dirs.len() > REVEAL_MANY_LIMIT {
+        return Err(format!(
+            "Trop de dossiers à ouvrir ({}), maximum {}",
+            dirs.len(),
+            REVEAL_MANY_LIMIT
+        ));
+    }
+
+    for dir in dirs {
+        if let Err(e) = open_directory(&dir) {
+            failures.push(RevealFailure {
+                path: dir.to_string_lossy().to_string(),
+                error: e,
+            });
+        }
     }
+
+    Ok(failures)
+}
+
+/// Open a directory in the platform file manager
+fn open_directory(dir: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
     #[cfg(target_os = "windows")]
-    {
-        Command::new("explorer")
-            .arg("/select,")
-            .arg(path.replace('/', "\\"))
-            .status()
-            .map_err(|e| e.to_string())?;
-    }
+    let mut cmd = Command::new("explorer");
     #[cfg(target_os = "linux")]
-    {
-        if let Some(dir) = Path::new(&path).parent() {
-            Command::new("xdg-open")
-                .arg(dir)
-                .status()
-                .map_err(|e| e.to_string())?;
-        }
+    let mut cmd = Command::new("xdg-open");
+
+    #[cfg(target_os = "windows")]
+    cmd.arg(to_windows_path(&dir.to_string_lossy()));
+    #[cfg(not(target_os = "windows"))]
+    cmd.arg(dir);
+
+    let status = cmd
+        .status()
+        .map_err(|e| format!("Impossible d'ouvrir le dossier {}: {}", dir.display(), e))?;
+
+    // explorer reports exit code 1 even when it opened the window
+    #[cfg(not(target_os = "windows"))]
+    if !status.success() {
+        return Err(format!("Impossible d'ouvrir le dossier {} ({})", dir.display(), status));
     }
+    #[cfg(target_os = "windows")]
+    let _ = status;
+
     Ok(())
 }
 
+/// Normalize separators for Windows shell tools, which reject forward slashes
+#[cfg(any(target_os = "windows", test))]
+fn to_windows_path(path: &str) -> String {
+    path.replace('/', "\\")
+}
+
+/// Build explorer's `/select,"PATH"` argument; quoting keeps spaces and commas intact
+#[cfg(any(target_os = "windows", test))]
+fn explorer_select_arg(win_path: &str) -> String {
+    format!("/select,\"{}\"", win_path)
+}
+
 #[tauri::command]
 async fn open_file(path: String) -> Result<(), String> {
     if !Path::new(&path).exists() {
@@ -541,9 +1624,39 @@ async fn open_file(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Size at which the log file is rotated
+const LOG_MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+/// Rotated log files kept alongside the current one
+const LOG_KEEP_FILES: usize = 5;
+
+/// Log file written by tauri-plugin-log's file target (named after the package)
+fn log_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = settings::log_dir(app)?;
+    Ok(dir.join(format!("{}.log", app.package_info().name)))
+}
+
+/// Path of the current log file, for bug reports
+#[tauri::command]
+fn get_log_path(app: tauri::AppHandle) -> Result<String, String> {
+    Ok(log_file_path(&app)?.to_string_lossy().to_string())
+}
+
+/// Reveal the current log file in the system file manager
+#[tauri::command]
+async fn open_log(app: tauri::AppHandle) -> Result<(), String> {
+    let path = log_file_path(&app)?;
+    if path.exists() {
+        return reveal_in_folder(path.to_string_lossy().to_string())
+            .await
+            .map_err(|e| e.message);
+    }
+    let dir = path.parent().ok_or("Chemin sans dossier")?;
+    open_directory(dir)
+}
+
 #[tauri::command]
 async fn open_logs_folder(app: tauri::AppHandle) -> Result<(), String> {
-    let log_dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    let log_dir = settings::log_dir(&app)?;
     
     if !log_dir.exists() {
         return Err("Dossier de logs introuvable".into());
@@ -575,9 +1688,18 @@ async fn open_logs_folder(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Open the resolved data folder (settings, cache, results), creating it if needed
+#[tauri::command]
+async fn open_data_dir(app: tauri::AppHandle) -> Result<(), String> {
+    let dir = settings::data_dir(&app);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Dossier de données inaccessible {}: {}", dir.display(), e))?;
+    open_directory(&dir)
+}
+
 #[tauri::command]
 async fn get_log_tail(lines: usize, app: tauri::AppHandle) -> Result<String, String> {
-    let log_dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    let log_dir = settings::log_dir(&app)?;
     
     if !log_dir.exists() {
         return Err("Dossier de logs introuvable".into());
@@ -620,34 +1742,72 @@ async fn get_log_tail(lines: usize, app: tauri::AppHandle) -> Result<String, Str
     Ok(tail)
 }
 
-#[tauri::command]
-async fn open_spectrum(path: String, app: tauri::AppHandle) -> Result<Vec<u8>, String> {
-    let src = Path::new(&path);
-    if !src.exists() {
-        return Err("Fichier introuvable".into());
-    }
+/// Error of a spectrogram run the analyzer couldn't complete, by the kind of failure
+fn spectrum_error(error: audio::AnalyzerError) -> CommandError {
+    let code = match error {
+        audio::AnalyzerError::Missing(_) => ErrorCode::AnalyzerNotFound,
+        audio::AnalyzerError::DepsMissing(_) => ErrorCode::PythonDepsMissing,
+        audio::AnalyzerError::Failed(_) => ErrorCode::SpectrumFailed,
+    };
+    CommandError::with_message(code, format!("whatsmybitrate execution failed: {}", error))
+}
 
+/// Where the spectrogram of `path` is rendered in the app cache dir. One PNG per track
+/// and purpose, so two spectra opened at once never share a file.
+fn spectrum_png_path(app: &tauri::AppHandle, path: &str, prefix: &str) -> Result<PathBuf, CommandError> {
     let temp_root = app.path().app_cache_dir().map_err(|e| e.to_string())?;
     if !temp_root.exists() {
         std::fs::create_dir_all(&temp_root).map_err(|e| e.to_string())?;
     }
-    let temp_root_str = temp_root.to_string_lossy();
+    Ok(temp_root.join(format!("{}-{:x}.png", prefix, md5::compute(path))))
+}
+
+/// Render a spectrogram PNG of `path`; size and frequency cap default to the analyzer's
+#[tauri::command]
+async fn open_spectrum(
+    path: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    max_freq_khz: Option<f64>,
+    app: tauri::AppHandle,
+) -> Result<Vec<u8>, CommandError> {
+    let src = Path::new(&path);
+    if !src.exists() {
+        return Err(CommandError::new(ErrorCode::FileNotFound));
+    }
+    let options = types::SpectrogramOptions {
+        width,
+        height,
+        max_freq_khz,
+    };
+    audio::validate_spectrogram_options(&options)
+        .map_err(|e| CommandError::with_message(ErrorCode::InvalidArgument, e))?;
+    let settings = load_settings(&app);
+    if let Err(e) = audio::analyzer_available(&app, &settings.analyzer_mode) {
+        return Err(CommandError::with_message(ErrorCode::AnalyzerNotFound, e));
+    }
+
+    let png_path = spectrum_png_path(&app, &path, "spectrum")?;
+    let png_str = audio::path_arg(&png_path)?;
 
     let result = audio::invoke_whatsmybitrate(
         &app,
-        "spectrum",
-        src.to_str().unwrap_or_default(),
+        &settings,
+        "spectrogram",
+        audio::path_arg(src)?,
         None,
-        Some(&temp_root_str),
+        Some(png_str),
+        Some(&options),
     ).await;
 
     match result {
         Ok(json) => {
              // Check if "error" key is present in the JSON response
             if let Some(err) = json.get("error").and_then(|s| s.as_str()) {
-                return Err(format!("whatsmybitrate failed: {}", err));
+                return Err(CommandError::with_message(ErrorCode::SpectrumFailed, format!("whatsmybitrate failed: {}", err)));
             }
              
+            // Trust the reported path: older analyzer builds named the image themselves
             let spectro_path = json.get("spectrogram_path").and_then(|s| s.as_str());
             if let Some(p) = spectro_path {
                  let bytes = std::fs::read(p).map_err(|e| format!("Failed to read generated spectrum: {e}"))?;
@@ -655,14 +1815,121 @@ async fn open_spectrum(path: String, app: tauri::AppHandle) -> Result<Vec<u8>, S
                  let _ = std::fs::remove_file(p); 
                  Ok(bytes)
             } else {
-                 Err("whatsmybitrate did not return a spectrogram path".into())
+                 Err(CommandError::with_message(ErrorCode::SpectrumFailed, "whatsmybitrate did not return a spectrogram path"))
             }
         },
-        Err(e) => Err(format!("whatsmybitrate execution failed: {}", e))
+        Err(e) => Err(spectrum_error(e))
+    }
+}
+
+/// Analyze `path` and render its spectrogram in a single analyzer run, for the
+/// inspector view. The PNG stays in the app cache dir so it can be shown by path.
+#[tauri::command]
+async fn inspect_file(path: String, app: tauri::AppHandle) -> Result<types::FileInspection, CommandError> {
+    let src = Path::new(&path);
+    if !src.is_file() {
+        return Err(CommandError::new(ErrorCode::FileNotFound));
+    }
+    let settings = load_settings(&app);
+    if let Err(e) = audio::analyzer_available(&app, &settings.analyzer_mode) {
+        return Err(CommandError::with_message(ErrorCode::AnalyzerNotFound, e));
+    }
+
+    let png_path = spectrum_png_path(&app, &path, "inspect")?;
+    let json = audio::invoke_whatsmybitrate(
+        &app,
+        &settings,
+        "spectrogram",
+        audio::path_arg(src)?,
+        None,
+        Some(audio::path_arg(&png_path)?),
+        None,
+    )
+    .await
+    .map_err(spectrum_error)?;
+
+    if let Some(err) = json.get("error").and_then(|s| s.as_str()) {
+        return Err(CommandError::with_message(ErrorCode::SpectrumFailed, format!("whatsmybitrate failed: {}", err)));
     }
+    let spectrogram_path = json
+        .get("spectrogram_path")
+        .and_then(|s| s.as_str())
+        .ok_or_else(|| {
+            CommandError::with_message(
+                ErrorCode::SpectrumFailed,
+                "whatsmybitrate did not return a spectrogram path",
+            )
+        })?;
+
+    let (bitrate, is_lossless, cutoff_hz) = audio::estimate_fields(&json);
+    Ok(types::FileInspection {
+        path: path.clone(),
+        bitrate,
+        is_lossless,
+        cutoff_hz,
+        spectrogram_path: spectrogram_path.to_string(),
+    })
 }
 
+/// Install the python whatsmybitrate requirements with `python3 -m pip install -r`,
+/// emitting each output line as "pip_install_progress"
+#[tauri::command]
+async fn install_python_deps(app: tauri::AppHandle) -> Result<(), String> {
+    use std::io::BufRead;
+    use std::process::{Command, Stdio};
+
+    let requirements = audio::find_requirements(&app)
+        .ok_or_else(|| "requirements.txt de whatsmybitrate introuvable".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let pip = Command::new("python3").args(["-m", "pip", "--version"]).output();
+        match pip {
+            Err(_) => return Err("python3 introuvable. Installez Python 3 puis réessayez.".to_string()),
+            Ok(out) if !out.status.success() => {
+                return Err("pip introuvable. Installez-le avec `python3 -m ensurepip --upgrade` puis réessayez.".to_string());
+            }
+            Ok(_) => {}
+        }
+
+        log::info!("[pip] Installing requirements from {:?}", requirements);
+        let mut child = Command::new("python3")
+            .args(["-m", "pip", "install", "-r"])
+            .arg(&requirements)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Impossible de lancer pip: {}", e))?;
+
+        // Drain stderr on its own thread so a chatty pip can't block on a full pipe
+        let stderr = child.stderr.take().map(|err| {
+            std::thread::spawn(move || {
+                std::io::BufReader::new(err)
+                    .lines()
+                    .map_while(Result::ok)
+                    .collect::<Vec<_>>()
+            })
+        });
+        if let Some(out) = child.stdout.take() {
+            for line in std::io::BufReader::new(out).lines().map_while(Result::ok) {
+                log::debug!("[pip] {}", line);
+                let _ = app.emit("pip_install_progress", &line);
+            }
+        }
 
+        let status = child.wait().map_err(|e| e.to_string())?;
+        let errors = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+        if status.success() {
+            log::info!("[pip] Requirements installed");
+            Ok(())
+        } else {
+            let tail = errors.iter().rev().take(5).rev().cloned().collect::<Vec<_>>().join("\n");
+            log::warn!("[pip] Install failed: {}", tail);
+            Err(format!("Échec de l'installation des dépendances Python:\n{}", tail))
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
 /// Response from auth status check
 #[derive(serde::Serialize)]
@@ -675,6 +1942,10 @@ struct AuthStatus {
 /// Register client with invite code
 #[tauri::command]
 async fn register_client(invite_code: String, app: tauri::AppHandle) -> Result<(), String> {
+    if is_registered(app.clone()) {
+        log::info!("[auth] Already registered, keeping the existing token");
+        return Ok(());
+    }
     let device_name = tauri_plugin_os::hostname();
     
     let result = tauri::async_runtime::spawn_blocking(move || {
@@ -690,7 +1961,13 @@ async fn register_client(invite_code: String, app: tauri::AppHandle) -> Result<(
                 "device_name": device_name
             }))
             .send()
-            .map_err(|e| format!("Registration request failed: {e}"))?;
+            .map_err(|e| {
+                if e.is_connect() || e.is_timeout() {
+                    "Serveur injoignable, vérifiez votre connexion.".to_string()
+                } else {
+                    format!("Registration request failed: {e}")
+                }
+            })?;
         
         if !resp.status().is_success() {
             let status = resp.status();
@@ -723,6 +2000,15 @@ async fn register_client(invite_code: String, app: tauri::AppHandle) -> Result<(
     Ok(())
 }
 
+/// Whether a client token is stored locally. Unlike check_auth_status this never
+/// contacts the server.
+#[tauri::command]
+fn is_registered(app: tauri::AppHandle) -> bool {
+    load_settings(&app)
+        .client_token
+        .is_some_and(|t| !t.is_empty())
+}
+
 /// Check if client is registered and get auth status
 /// Validates token with server if present
 #[tauri::command]
@@ -888,7 +2174,6 @@ async fn search_tracks(query: String, app: tauri::AppHandle) -> Result<Vec<Searc
 
 fn main() {
     log_panics::init();
-    init_rayon_pool();
     tauri::Builder::default()
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_process::init())
@@ -896,10 +2181,20 @@ fn main() {
         .plugin(tauri_plugin_log::Builder::default()
             .targets([
                 tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stdout),
-                tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::LogDir { file_name: None }),
+                tauri_plugin_log::Target::new(match settings::portable_data_dir() {
+                    Some(dir) => tauri_plugin_log::TargetKind::Folder {
+                        path: dir.join("logs"),
+                        file_name: None,
+                    },
+                    None => tauri_plugin_log::TargetKind::LogDir { file_name: None },
+                }),
                 tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Webview),
             ])
-            .level(log::LevelFilter::Info)
+            // Everything passes the plugin; the effective level is the `log_level`
+            // setting, applied with log::set_max_level in setup and on save
+            .level(log::LevelFilter::Trace)
+            .max_file_size(LOG_MAX_FILE_BYTES)
+            .rotation_strategy(tauri_plugin_log::RotationStrategy::KeepSome(LOG_KEEP_FILES))
             .filter(|metadata| {
                 // Silence reqwest and hyper trace/debug logs
                 if metadata.target().starts_with("reqwest") || metadata.target().starts_with("hyper") {
@@ -915,7 +2210,25 @@ fn main() {
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(watcher::WatcherState::default())
+        .manage(results::ResultsState::default())
+        .manage(cache::ActiveCaches::default())
+        .manage(scan_state::ScanTracker::default())
         .setup(|_app| {
+            let settings = load_settings(_app.handle());
+            log::set_max_level(settings.log_level_filter());
+            // Detection runs before the log plugin exists, so it's reported here
+            if let Some(dir) = settings::portable_data_dir() {
+                log::info!("[settings] Portable mode, data in {:?}", dir);
+            }
+            init_rayon_pool(settings.rayon_threads);
+
+            // Resolving the analyzer logs a warning when `analyzer_mode` can't be honored
+            let handle = _app.handle().clone();
+            std::thread::spawn(move || {
+                audio::check_dependencies(&handle);
+            });
+
             // Only register updater plugin if with-updater feature is enabled
             #[cfg(feature = "with-updater")]
             {
@@ -935,49 +2248,147 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             queue_stats,
             download_link,
+            download_from_clipboard,
             scan_folder,
+            scan_summary,
+            estimate_scan,
             reveal_in_folder,
+            open_containing_folder,
+            reveal_many,
             open_file,
             open_spectrum,
+            inspect_file,
+            install_python_deps,
             get_settings,
             save_settings,
+            settings::get_data_dir,
             redownload_bad,
             download_with_url,
             accept_redownload,
             discard_file,
             revert_replacement,
             extract_cover,
+            write_tags,
+            write_analysis_tag,
+            mark_replaced,
+            verify_replaced,
             register_client,
             check_auth_status,
+            is_registered,
             open_logs_folder,
+            open_data_dir,
             get_log_tail,
-            search_tracks
+            search_tracks,
+            probe_file,
+            check_dependencies,
+            tool_versions,
+            analyze_windows,
+            analyze_file_json,
+            probe_durations,
+            diagnose_analysis,
+            analyze_files,
+            reanalyze_file,
+            retry_errors,
+            get_log_path,
+            open_log,
+            results::query_results,
+            results::load_last_scan,
+            results::compute_histogram,
+            results::diff_scans,
+            cache::library_stats,
+            cache::prune_cache,
+            reference::quality_reference,
+            duplicates::find_duplicates,
+            duplicates::compare_files,
+            replaygain::compute_replaygain,
+            scan_state::scan_state,
+            scan_state::cancel_scan,
+            trash_file,
+            organize::organize_file,
+            watcher::watch_folder,
+            watcher::unwatch_folder
         ])
-
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Stop scans first so nothing new is spawned, then kill what is running
+                // and save what the interrupted scans had analyzed so far
+                audio::SCAN_CANCELLED.store(true, Ordering::SeqCst);
+                app.state::<watcher::WatcherState>().stop_all();
+                analyzer_worker::shutdown();
+                processes::kill_all();
+                app.state::<cache::ActiveCaches>().flush();
+            }
+        });
 }
 
-fn init_rayon_pool() {
-    let threads = std::env::var("RAYON_NUM_THREADS")
+/// Thread count for the global rayon pool: the `rayon_threads` setting when > 0,
+/// then RAYON_NUM_THREADS, then the number of CPUs
+pub(crate) fn resolve_thread_count(setting: usize) -> usize {
+    if setting > 0 {
+        return setting;
+    }
+    std::env::var("RAYON_NUM_THREADS")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
         .filter(|&n| n > 0)
-        .unwrap_or_else(|| std::cmp::max(1, num_cpus::get()));
+        .unwrap_or_else(|| std::cmp::max(1, num_cpus::get()))
+}
+
+/// Build the global rayon pool. It can only be built once per process,
+/// so changing `rayon_threads` requires a restart.
+fn init_rayon_pool(setting: usize) {
+    let threads = resolve_thread_count(setting);
+    match ThreadPoolBuilder::new().num_threads(threads).build_global() {
+        Ok(_) => log::info!("[rayon] Global pool built with {} threads", threads),
+        Err(e) => log::warn!("[rayon] Global pool already initialized: {}", e),
+    }
+}
 
-    let _ = ThreadPoolBuilder::new().num_threads(threads).build_global();
+/// File name prefix of redownloads waiting to be checked
+pub(crate) const CANDIDATE_PREFIX: &str = "ksi-candidate-";
+
+/// Where a redownload is saved until it has been checked, next to the original but
+/// never on top of it
+fn candidate_path(parent: &Path, filename: &str) -> PathBuf {
+    parent.join(format!("{}{}", CANDIDATE_PREFIX, filename))
 }
 
-fn init_rayon_pool_with(threads: usize) {
-    if rayon::current_num_threads() > 0 {
-        return;
+/// Analyze a downloaded candidate against the file it would replace. Err holds the
+/// warning for the user when the candidate isn't an improvement.
+fn verify_upgrade(original: &Path, candidate: &Path, app: &tauri::AppHandle) -> Result<(), String> {
+    let new_quality = analyze_file_quality(candidate, app)
+        .map_err(|e| format!("Analyse du fichier téléchargé impossible ({}), original conservé.", e))?;
+    let old_quality = analyze_file_quality(original, app)
+        .map_err(|e| log::warn!("[GUI] Could not analyze original {:?}: {}", original, e))
+        .ok();
+
+    if audio::is_quality_upgrade(old_quality.as_ref(), &new_quality) {
+        return Ok(());
+    }
+    let old = old_quality.map_or_else(|| "Unknown".to_string(), |q| q.quality_string);
+    log::info!(
+        "[GUI] Candidate {:?} ({}) is not better than {:?} ({}), discarding",
+        candidate, new_quality.quality_string, original, old
+    );
+    Err(format!(
+        "Le fichier téléchargé ({}) n'est pas meilleur que l'original ({}), original conservé.",
+        new_quality.quality_string, old
+    ))
+}
+
+/// RedownloadResult for a candidate that was discarded, the original left as is
+fn rejected_redownload(original_path: String, original_duration: Option<f64>, warning: String) -> RedownloadResult {
+    RedownloadResult {
+        original_path,
+        new_path: String::new(),
+        original_duration,
+        new_duration: None,
+        cover_url: None,
+        new_bitrate: None,
+        warning: Some(warning),
     }
-    let count = if threads > 0 {
-        threads
-    } else {
-        std::cmp::max(1, num_cpus::get())
-    };
-    let _ = ThreadPoolBuilder::new().num_threads(count).build_global();
 }
 
 #[tauri::command]
@@ -986,12 +2397,10 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
     let client_token = settings.client_token.clone()
         .filter(|t| !t.is_empty())
         .ok_or_else(|| "Non enregistré. Veuillez entrer votre code d'invitation.".to_string())?;
+    let rate_limit = settings.download_rate_limit();
     
     tauri::async_runtime::spawn_blocking(move || {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
-            .build()
-            .map_err(|e| format!("Client build failed: {e}"))?;
+        let client = download_client()?;
         
         log::info!("[GUI] Using Core API: {}", CORE_API_URL);
         let mut downloaded = Vec::new();
@@ -1063,7 +2472,7 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
             let download_url = match download_target {
                 Some(url) => url,
                 None => {
-                    log::error!("[GUI] Skipping '{}' - no automatic match", stem);
+                    log::info!("[GUI] Skipping '{}' - no automatic match", stem);
                     continue;
                 }
             };
@@ -1099,22 +2508,28 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
                                 let file_url = format!("{}{}", CORE_API_URL, rel_url);
                                 let final_filename = json["filename"].as_str().unwrap_or("downloaded.mp3");
                                 let dest_path = parent.join(final_filename);
+                                let candidate = candidate_path(&parent, final_filename);
 
                                 match client.get(&file_url)
                                      .header("X-Client-Token", &client_token)
                                      .send() {
                                      Ok(mut file_resp) => {
-                                         if let Ok(mut file) = fs::File::create(&dest_path) {
-                                             if let Err(e) = file_resp.copy_to(&mut file) {
+                                         if let Ok(mut file) = fs::File::create(&candidate) {
+                                             if let Err(e) = save_download(&mut file_resp, &mut file, rate_limit) {
                                                  log::error!("[GUI] Failed to write file: {}", e);
                                              } else {
                                                  // Explicitly sync file to disk before probing (fixes macOS race condition)
                                                  let _ = file.sync_all();
                                                  drop(file); // Ensure file handle is closed
-                                                 log::info!("[GUI] Downloaded to: {:?}", dest_path);
+                                                 log::info!("[GUI] Downloaded to: {:?}", candidate);
                                                  
                                                  let original_dur = probe_duration(&path, &app);
-                                                 let new_dur = probe_duration(&dest_path, &app);
+                                                 if let Err(warning) = verify_upgrade(&path, &candidate, &app) {
+                                                     let _ = fs::remove_file(&candidate);
+                                                     downloaded.push(rejected_redownload(path_str.clone(), original_dur, warning));
+                                                     continue;
+                                                 }
+                                                 let new_dur = probe_duration(&candidate, &app);
 
                                                  let tolerance_sec = 2.0;
                                                  let tolerance_pct = 0.05;
@@ -1126,7 +2541,7 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
                                                  };
                                                  let is_match = diff <= tolerance_sec || rel <= tolerance_pct;
 
-                                                 if is_match && dest_path != path {
+                                                 if is_match {
                                                      if backup && path.exists() {
                                                          let backup_dir = parent.join("backup-ksi");
                                                          if !backup_dir.exists() {
@@ -1145,8 +2560,20 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
                                                          log::info!("[GUI] Auto-replaced original file (durations matched)");
                                                      }
                                                  }
+                                                 // A mismatched download never takes the original's name
+                                                 let dest_path = if is_match || dest_path != path {
+                                                     match fs::rename(&candidate, &dest_path) {
+                                                         Ok(()) => dest_path,
+                                                         Err(e) => {
+                                                             log::error!("[GUI] Failed to move download into place: {}", e);
+                                                             candidate
+                                                         }
+                                                     }
+                                                 } else {
+                                                     candidate
+                                                 };
 
-                                                 let new_bitrate = probe_bitrate(&dest_path, &app);
+                                                 let new_bitrate = probe_bitrate(&dest_path, &app, &settings);
 
                                                  // Write KESON_REPLACED tag to mark file as replaced
                                                  if let Err(e) = tagging::write_replaced_tag(&dest_path) {
@@ -1160,6 +2587,7 @@ async fn redownload_bad(paths: Vec<String>, source: String, backup: bool, app: t
                                                      new_duration: new_dur,
                                                      cover_url: cover_url.clone(),
                                                      new_bitrate,
+                                                     warning: None,
                                                  });
                                              }
                                          }
@@ -1183,12 +2611,10 @@ async fn download_with_url(original_path: String, url: String, backup: bool, app
     let client_token = settings.client_token.clone()
         .filter(|t| !t.is_empty())
         .ok_or_else(|| "Non enregistré. Veuillez entrer votre code d'invitation.".to_string())?;
+    let rate_limit = settings.download_rate_limit();
     
     tauri::async_runtime::spawn_blocking(move || {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
-            .build()
-            .map_err(|e| format!("Client build failed: {e}"))?;
+        let client = download_client()?;
         
         log::info!("[GUI] Using Core API: {}", CORE_API_URL);
         
@@ -1225,6 +2651,7 @@ async fn download_with_url(original_path: String, url: String, backup: bool, app
             .ok_or_else(|| "No downloadUrl in response".to_string())?;
         let final_filename = json["filename"].as_str().unwrap_or("downloaded.mp3");
         let dest_path = parent.join(final_filename);
+        let candidate = candidate_path(&parent, final_filename);
 
         let file_url = format!("{}{}", CORE_API_URL, rel_url);
         let mut file_resp = client.get(&file_url)
@@ -1232,26 +2659,41 @@ async fn download_with_url(original_path: String, url: String, backup: bool, app
             .send()
             .map_err(|e| format!("Failed to fetch file: {}", e))?;
             
-        let mut file = fs::File::create(&dest_path)
+        let mut file = fs::File::create(&candidate)
             .map_err(|e| format!("Failed to create file: {}", e))?;
             
-        file_resp.copy_to(&mut file)
+        save_download(&mut file_resp, &mut file, rate_limit)
             .map_err(|e| format!("Failed to write file: {}", e))?;
 
         // Explicitly sync file to disk before probing (fixes macOS race condition)
         file.sync_all().map_err(|e| format!("Failed to sync file: {}", e))?;
         drop(file); // Ensure file handle is closed
         
-        log::info!("[GUI] Downloaded to: {:?}", dest_path);
+        log::info!("[GUI] Downloaded to: {:?}", candidate);
         
         log::info!("[GUI] Probing original duration for: {:?}", path);
         let original_dur = probe_duration(&path, &app).unwrap_or(0.0);
         log::info!("[GUI] Original duration: {}", original_dur);
 
-        log::info!("[GUI] Probing new duration for: {:?}", dest_path);
-        let new_dur = probe_duration(&dest_path, &app).unwrap_or(0.0);
+        if let Err(warning) = verify_upgrade(&path, &candidate, &app) {
+            let _ = fs::remove_file(&candidate);
+            return Ok(rejected_redownload(original_path, Some(original_dur), warning));
+        }
+
+        log::info!("[GUI] Probing new duration for: {:?}", candidate);
+        let new_dur = probe_duration(&candidate, &app).unwrap_or(0.0);
         log::info!("[GUI] New duration: {}", new_dur);
 
+        // With a backup the candidate replaces the original below. Without one the
+        // original stays, so the candidate keeps its name rather than land on top of it.
+        let dest_path = if !backup && dest_path != path {
+            fs::rename(&candidate, &dest_path)
+                .map_err(|e| format!("Failed to move download into place: {}", e))?;
+            dest_path
+        } else {
+            candidate
+        };
+
         if backup {
              let backup_dir = parent.join("backup-ksi");
              if !backup_dir.exists() {
@@ -1278,7 +2720,7 @@ async fn download_with_url(original_path: String, url: String, backup: bool, app
         }
 
         let new_file_path = if backup { &path } else { &dest_path };
-        let new_bitrate = probe_bitrate(new_file_path, &app);
+        let new_bitrate = probe_bitrate(new_file_path, &app, &settings);
 
         // Write KESON_REPLACED tag to mark file as replaced
         if let Err(e) = tagging::write_replaced_tag(new_file_path) {
@@ -1292,6 +2734,7 @@ async fn download_with_url(original_path: String, url: String, backup: bool, app
             new_duration: Some(new_dur),
             cover_url: json["metadata"]["thumbnail"].as_str().map(|s| s.to_string().replace("url(\"", "").replace("\")", "")),
             new_bitrate,
+            warning: None,
         })
     }).await.map_err(|e| e.to_string())?
 }
@@ -1336,7 +2779,7 @@ async fn revert_replacement(original_path: String) -> Result<bool, String> {
 
 #[tauri::command]
 fn accept_redownload(app: tauri::AppHandle, original: String, new_path: String) -> Result<String, String> {
-    log::error!("[accept_redownload] Request to replace '{}' with '{}'", original, new_path);
+    log::info!("[accept_redownload] Request to replace '{}' with '{}'", original, new_path);
     let orig = PathBuf::from(&original);
     let newp = PathBuf::from(&new_path);
 
@@ -1365,7 +2808,7 @@ fn accept_redownload(app: tauri::AppHandle, original: String, new_path: String)
                 let filename = orig.file_name().unwrap_or_default();
                 let backup_path = backup_dir.join(filename);
                 
-                log::error!("[accept_redownload] Backing up original to: {:?}", backup_path);
+                log::info!("[accept_redownload] Backing up original to: {:?}", backup_path);
                 
                 // If backup already exists, maybe overwrite or rename? 
                 // For now, let's just overwrite backup (standard behavior for simple bak)
@@ -1376,17 +2819,17 @@ fn accept_redownload(app: tauri::AppHandle, original: String, new_path: String)
         }
     }
 
-    log::error!("[accept_redownload] Renaming new file to original...");
+    log::debug!("[accept_redownload] Renaming new file to original...");
     match fs::rename(&newp, &orig) {
         Ok(_) => {
-             log::error!("[accept_redownload] Success");
+             log::info!("[accept_redownload] Success");
              
              // Invalidate cache for this file
              let settings = load_settings(&app); // pass reference to app
              if let Ok(path) = cache_path(&app) {
                   let mut cache = load_cache(&path, settings.cache_max_entries);
                   if cache.remove(&orig.to_string_lossy().to_string()).is_some() {
-                      log::error!("[accept_redownload] Invalidated cache for: {:?}", orig);
+                      log::debug!("[accept_redownload] Invalidated cache for: {:?}", orig);
                       let _ = save_cache(&path, &cache);
                   }
              }
@@ -1409,7 +2852,397 @@ fn discard_file(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Move a file to the OS trash instead of deleting it. Returns its location in the
+/// trash when the platform exposes it.
+#[tauri::command]
+async fn trash_file(path: String) -> Result<Option<String>, String> {
+    let p = PathBuf::from(&path);
+    if !p.exists() {
+        return Err("Fichier introuvable".into());
+    }
+    if p.is_dir() {
+        return Err("Seuls les fichiers peuvent être mis à la corbeille, pas les dossiers".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || trash::move_to_trash(&p))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|location| location.map(|l| l.to_string_lossy().to_string()))
+}
+
+/// Analyze a single file (e.g. drag-and-dropped) without touching the cache
+#[tauri::command]
+async fn probe_file(path: String, app: tauri::AppHandle) -> Result<audio::QualityAnalysisResult, String> {
+    let p = PathBuf::from(&path);
+    if !p.exists() {
+        return Err("Fichier introuvable".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || analyze_file_quality(&p, &app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// ffprobe processes probe_durations runs at once; probes wait on process startup and
+/// disk more than on the CPU, so this is independent of the scan thread count
+const PROBE_DURATIONS_THREADS: usize = 6;
+
+/// Duration in seconds of each file, probed in parallel on a small dedicated pool so a
+/// running scan keeps its threads. Files that are missing or fail to probe map to None.
+#[tauri::command]
+async fn probe_durations(
+    paths: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<HashMap<String, Option<f64>>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(PROBE_DURATIONS_THREADS.min(paths.len()).max(1))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(pool.install(|| {
+            paths
+                .par_iter()
+                .map(|path| {
+                    let p = Path::new(path);
+                    let duration = if p.is_file() { probe_duration(p, &app) } else { None };
+                    (path.clone(), duration)
+                })
+                .collect()
+        }))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Run the analyzer once on a file and return everything it printed, with the resolved
+/// program, the exact argv and the exit code, to debug a misconfigured analyzer
+#[tauri::command]
+async fn diagnose_analysis(path: String, app: tauri::AppHandle) -> Result<types::AnalyzerDiagnostics, String> {
+    let p = PathBuf::from(&path);
+    if !p.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || audio::diagnose_analysis(&app, &p))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Maximum number of windows analyze_windows accepts in one call
+const ANALYZE_WINDOWS_LIMIT: usize = 12;
+
+/// Analyze one file and return the analyzer's JSON untouched (whatsmybitrate's full
+/// to_dict), for scripts that need more than the fields a scan keeps. Uses the
+/// file's analysis window and the global start offset; the cache is bypassed.
+#[tauri::command]
+async fn analyze_file_json(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+    let p = PathBuf::from(&path);
+    if !p.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    let settings = load_settings(&app);
+    let (_, window) = settings.thresholds_for(&p, None, None);
+    audio::invoke_whatsmybitrate(
+        &app,
+        &settings,
+        "analyze",
+        audio::path_arg(&p)?,
+        Some(window),
+        None,
+        None,
+    )
+    .await
+    .map_err(String::from)
+}
+
+/// Analyze one file with several analysis windows to check how stable the estimate is
+#[tauri::command]
+async fn analyze_windows(
+    path: String,
+    windows: Vec<u32>,
+    app: tauri::AppHandle,
+) -> Result<Vec<types::WindowAnalysis>, String> {
+    let p = PathBuf::from(&path);
+    if !p.exists() {
+        return Err("Fichier introuvable".into());
+    }
+    if windows.is_empty() || windows.contains(&0) {
+        return Err("Fenêtres d'analyse invalides".into());
+    }
+    if windows.len() > ANALYZE_WINDOWS_LIMIT {
+        return Err(format!(
+            "Trop de fenêtres ({}), maximum {}",
+            windows.len(),
+            ANALYZE_WINDOWS_LIMIT
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || audio::analyze_windows(&p, &app, &windows))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Report which external tools (ffprobe, ffmpeg, whatsmybitrate) are available
+#[tauri::command]
+async fn check_dependencies(app: tauri::AppHandle) -> Result<types::DependencyReport, String> {
+    tauri::async_runtime::spawn_blocking(move || audio::check_dependencies(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Versions of ffprobe, ffmpeg and whatsmybitrate, for bug reports
+#[tauri::command]
+async fn tool_versions(app: tauri::AppHandle) -> Result<types::ToolVersions, String> {
+    tauri::async_runtime::spawn_blocking(move || audio::tool_versions(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Write corrected artist/title/album to a file. Ok(false) if its format can't be tagged.
+#[tauri::command]
+async fn write_tags(path: String, metadata: types::ExtractedMetadata) -> Result<bool, String> {
+    let path = PathBuf::from(&path);
+    if !path.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || tagging::write_tags(&path, &metadata))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Embed an analysis in a file as a KESON_ANALYSIS comment, tagged with the current
+/// analyzer version. Ok(false) if its format can't be tagged.
+#[tauri::command]
+async fn write_analysis_tag(
+    path: String,
+    bitrate: Option<u32>,
+    cutoff: Option<u32>,
+    is_lossless: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<bool, String> {
+    let path = PathBuf::from(&path);
+    if !path.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry = CacheEntry {
+            bitrate,
+            is_lossless,
+            note: None,
+            cutoff_hz: cutoff,
+            analyzer_version: audio::tool_versions(&app).whatsmybitrate,
+            ..Default::default()
+        };
+        tagging::write_analysis_tag(&path, &entry)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Write the KESON_REPLACED tag to each file, one after the other (tag writes rewrite
+/// the file, so they are kept off the thread pool). A failure only affects its own
+/// file. Emits "mark_replaced_progress" after each one.
+#[tauri::command]
+async fn mark_replaced(
+    paths: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<Vec<types::TagWriteOutcome>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = paths.len();
+        let mut outcomes = Vec::with_capacity(total);
+        for (i, path) in paths.into_iter().enumerate() {
+            let p = Path::new(&path);
+            let state = app.state::<results::ResultsState>();
+            let result = if !p.is_file() {
+                Err("Fichier introuvable".to_string())
+            } else if let Err(e) = state.check_replaceable(&path) {
+                Err(e)
+            } else {
+                match tagging::write_replaced_tag(p) {
+                    Ok(true) => state.set_replaced(&path),
+                    Ok(false) => Err("Format non pris en charge pour le tag".to_string()),
+                    Err(e) => Err(e),
+                }
+            };
+            if let Err(e) = &result {
+                log::warn!("[tagging] Could not mark {:?} as replaced: {}", path, e);
+            }
+            let _ = app.emit(
+                "mark_replaced_progress",
+                types::BatchProgress {
+                    done: i + 1,
+                    total,
+                    path: path.clone(),
+                },
+            );
+            outcomes.push(types::TagWriteOutcome {
+                path,
+                tagged: result.is_ok(),
+                error: result.err(),
+            });
+        }
+        outcomes
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Read-only audit of the KESON_REPLACED tag on each file. Tagged files are also looked
+/// up in the analysis cache, and listed as tagged-but-bad when the cached estimate is
+/// lossy and below their threshold. Files never analyzed since tagging can't be judged.
+#[tauri::command]
+async fn verify_replaced(paths: Vec<String>, app: tauri::AppHandle) -> Result<types::ReplacedAudit, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = load_settings(&app);
+        let cache = load_cache(&cache_path(&app)?, 0);
+
+        // (tagged, still bad), None when the file is gone
+        let checks: Vec<Option<(bool, bool)>> = paths
+            .par_iter()
+            .map(|path| {
+                let p = Path::new(path);
+                if !p.is_file() {
+                    return None;
+                }
+                if !tagging::has_replaced_tag(p) {
+                    return Some((false, false));
+                }
+                let (min, _) = settings.thresholds_for(p, None, None);
+                let still_bad = audio::file_hash(p)
+                    .ok()
+                    .and_then(|hash| cache.get(&hash))
+                    .is_some_and(|entry| {
+                        entry.is_lossless != Some(true) && entry.bitrate.is_some_and(|b| b < min)
+                    });
+                Some((true, still_bad))
+            })
+            .collect();
+
+        let mut audit = types::ReplacedAudit::default();
+        for (path, check) in paths.into_iter().zip(checks) {
+            match check {
+                None => audit.missing.push(path),
+                Some((false, _)) => audit.untagged.push(path),
+                Some((true, still_bad)) => {
+                    if still_bad {
+                        audit.tagged_but_bad.push(path.clone());
+                    }
+                    audit.tagged.push(path);
+                }
+            }
+        }
+        Ok(audit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Embedded front cover of a local file as a base64 data URL, or None if it has none
 #[tauri::command]
-fn extract_cover(audio_path: String, app: tauri::AppHandle) -> Result<Option<String>, String> {
-    extract_embedded_cover(&audio_path, &app)
+async fn extract_cover(audio_path: String) -> Result<Option<types::CoverArt>, String> {
+    use base64::Engine;
+
+    let path = PathBuf::from(&audio_path);
+    if !path.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    let cover = tauri::async_runtime::spawn_blocking(move || tagging::read_front_cover(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    Ok(cover.map(|(data, mime_type)| types::CoverArt {
+        data_url: format!(
+            "data:{};base64,{}",
+            mime_type,
+            base64::engine::general_purpose::STANDARD.encode(&data)
+        ),
+        mime_type,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explorer_select_arg_keeps_spaces_and_unicode() {
+        let win_path = to_windows_path("C:/Users/José/My Music/track 01.flac");
+        assert_eq!(win_path, "C:\\Users\\José\\My Music\\track 01.flac");
+        assert_eq!(
+            explorer_select_arg(&win_path),
+            "/select,\"C:\\Users\\José\\My Music\\track 01.flac\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_library_follows_symlinked_folders_without_looping() {
+        let base = std::env::temp_dir().join(format!("ksi_symlink_test_{}", std::process::id()));
+        let root = base.join("library");
+        let elsewhere = base.join("elsewhere");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&elsewhere).unwrap();
+        fs::write(elsewhere.join("track.mp3"), b"audio").unwrap();
+        std::os::unix::fs::symlink(&elsewhere, root.join("linked")).unwrap();
+        // A link back up the tree must not make the walk recurse forever
+        std::os::unix::fs::symlink(&base, elsewhere.join("loop")).unwrap();
+
+        let files = |follow: bool| -> Vec<PathBuf> {
+            walk_library(&root, true, follow)
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect()
+        };
+        assert!(files(false).is_empty());
+        assert_eq!(files(true), vec![root.join("linked").join("track.mp3")]);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_scan_scope_size_band() {
+        let scope = ScanScope::new(None, Some(1_000), Some(5_000)).unwrap();
+        assert!(!scope.size_in_band(999));
+        assert!(scope.size_in_band(1_000) && scope.size_in_band(5_000));
+        assert!(!scope.size_in_band(5_001));
+        assert!(ScanScope::default().size_in_band(0));
+        assert!(ScanScope::new(None, Some(10), Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_clipboard_url_accepts_only_a_bare_http_link() {
+        assert_eq!(
+            clipboard_url("  https://soundcloud.com/artist/track\n").unwrap(),
+            "https://soundcloud.com/artist/track"
+        );
+        assert!(clipboard_url("").is_err());
+        assert!(clipboard_url("file:///etc/passwd").is_err());
+        assert!(clipboard_url("écoute https://soundcloud.com/a/b").is_err());
+    }
+
+    #[test]
+    fn test_root_looks_too_broad_needs_many_files_and_few_audio() {
+        assert!(root_looks_too_broad(500_000, 200));
+        // Big music libraries walk many files, but mostly audio
+        assert!(!root_looks_too_broad(500_000, 40_000));
+        // Small folders are never flagged, however empty of audio
+        assert!(!root_looks_too_broad(5_000, 0));
+    }
+
+    #[test]
+    fn test_throttle_delay_keeps_downloads_under_the_limit() {
+        use std::time::Duration;
+        // 125 kB is one second's worth at 1000 kbps
+        assert_eq!(throttle_delay(125_000, Duration::from_millis(400), 1000), Duration::from_millis(600));
+        assert_eq!(throttle_delay(125_000, Duration::from_secs(2), 1000), Duration::ZERO);
+        // Even at 1 kbps, the slowest limit, a chunk's pause stays under the idle timeout
+        assert!(throttle_delay(DOWNLOAD_CHUNK_BYTES as u64, Duration::ZERO, 1) < DOWNLOAD_IDLE_TIMEOUT);
+    }
+
+    #[test]
+    fn test_telemetry_is_due_once_per_interval() {
+        let last = AtomicU64::new(0);
+        assert!(!telemetry_due(&last, 400));
+        assert!(telemetry_due(&last, 1200));
+        // Another thread finishing a file at the same moment doesn't emit again
+        assert!(!telemetry_due(&last, 1200));
+        assert!(!telemetry_due(&last, 2100));
+        assert!(telemetry_due(&last, 2200));
+    }
 }
@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::extract_metadata_from_file;
+use crate::types::ExtractedMetadata;
+
+/// Characters that cannot appear in a file name component on this platform
+#[cfg(target_os = "windows")]
+const ILLEGAL_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+#[cfg(target_os = "macos")]
+const ILLEGAL_CHARS: &[char] = &['/', ':'];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const ILLEGAL_CHARS: &[char] = &['/'];
+
+/// Device names Windows refuses as file names, with or without an extension
+#[cfg(target_os = "windows")]
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make one path component safe: replace illegal and control characters with `_`,
+/// and trim the spaces and dots Windows silently strips
+pub fn sanitize_component(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| {
+            if ILLEGAL_CHARS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let trimmed = replaced.trim().trim_end_matches('.').trim_end();
+    let name = if trimmed.is_empty() || trimmed == "." || trimmed == ".." {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    };
+
+    #[cfg(target_os = "windows")]
+    {
+        let stem = name.split('.').next().unwrap_or("");
+        if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            return format!("_{}", name);
+        }
+    }
+    name
+}
+
+/// Fill `{artist}`, `{album}` and `{title}` in a `/`-separated template. Each component
+/// is sanitized after substitution, so a "/" inside a tag never creates a folder.
+pub fn render_template(template: &str, meta: &ExtractedMetadata, fallback_title: &str) -> PathBuf {
+    let value = |v: &Option<String>, default: &str| {
+        v.as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(default)
+            .to_string()
+    };
+    let artist = value(&meta.artist, "Unknown Artist");
+    let album = value(&meta.album, "Unknown Album");
+    let title = value(&meta.title, fallback_title);
+
+    template
+        .split(['/', '\\'])
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| {
+            sanitize_component(
+                &part
+                    .replace("{artist}", &artist)
+                    .replace("{album}", &album)
+                    .replace("{title}", &title),
+            )
+        })
+        .collect()
+}
+
+/// `path` if free, otherwise `name (2).ext`, `name (3).ext`... in the same folder
+pub fn unique_destination(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded counter always finds a free name")
+}
+
+/// Move a file, copying then removing when a plain rename crosses filesystems
+pub fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| format!("Impossible de copier le fichier: {}", e))?;
+    fs::remove_file(from).map_err(|e| format!("Impossible de supprimer l'original: {}", e))
+}
+
+/// Replace a leading `~` (alone or followed by a separator) with the home directory
+pub fn expand_tilde(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home.to_path_buf(),
+        (Some(rest), Some(home)) if rest.starts_with(['/', '\\']) => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Split an output directory at its first templated component: the fixed part can
+/// be created up front, the rest (e.g. `{artist}/{album}`) only once tags are known
+pub fn split_output_template(dir: &Path) -> (PathBuf, Option<String>) {
+    let mut base = PathBuf::new();
+    let mut rest: Vec<String> = Vec::new();
+    for component in dir.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if !rest.is_empty() || part.contains('{') {
+            rest.push(part.to_string());
+        } else {
+            base.push(component);
+        }
+    }
+    let rest = (!rest.is_empty()).then(|| rest.join("/"));
+    (base, rest)
+}
+
+/// Move a track under `root` following `template` (e.g. `{artist}/{album}/{title}`),
+/// filled from its tags. Never overwrites: a counter is appended instead.
+#[tauri::command]
+pub async fn organize_file(
+    path: String,
+    root: String,
+    template: String,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let source = PathBuf::from(&path);
+    if !source.is_file() {
+        return Err("Fichier introuvable".into());
+    }
+    let root = PathBuf::from(&root);
+    if !root.is_dir() {
+        return Err("Dossier introuvable".into());
+    }
+    if !template.contains('{') {
+        return Err("Modèle invalide: aucun champ ({artist}, {album}, {title})".into());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let meta = extract_metadata_from_file(&source, &app);
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut relative = render_template(&template, &meta, &stem);
+        if let Some(ext) = source.extension() {
+            let mut name = relative.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+            name.push(".");
+            name.push(ext);
+            relative.set_file_name(name);
+        }
+
+        let target = root.join(relative);
+        if target == source {
+            return Ok(target.to_string_lossy().to_string());
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Impossible de créer le dossier {}: {}", parent.display(), e))?;
+        }
+        let target = unique_destination(&target);
+        move_file(&source, &target)?;
+        log::info!("[organize] Moved {:?} to {:?}", source, target);
+        Ok(target.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template_sanitizes_each_component() {
+        let meta = ExtractedMetadata {
+            artist: Some("AC/DC".into()),
+            album: None,
+            title: Some("Back in Black ".into()),
+            ..Default::default()
+        };
+        let path = render_template("{artist}/{album}/{title}", &meta, "file");
+        let parts: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(parts, vec!["AC_DC", "Unknown Album", "Back in Black"]);
+    }
+
+    #[test]
+    fn test_unique_destination_appends_counter() {
+        let dir = std::env::temp_dir().join(format!("ksi_organize_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let taken = dir.join("Song.mp3");
+        fs::write(&taken, b"x").unwrap();
+
+        assert_eq!(unique_destination(&taken), dir.join("Song (2).mp3"));
+        assert_eq!(unique_destination(&dir.join("Free.mp3")), dir.join("Free.mp3"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_expand_tilde_uses_home_directory() {
+        let home = dirs::home_dir().expect("home directory on this platform");
+        assert_eq!(expand_tilde("~", Some(&home)), home);
+        assert_eq!(expand_tilde("~/Music", Some(&home)), home.join("Music"));
+        assert_eq!(expand_tilde("~user/Music", Some(&home)), PathBuf::from("~user/Music"));
+        assert_eq!(expand_tilde("/srv/Music", Some(&home)), PathBuf::from("/srv/Music"));
+        assert_eq!(expand_tilde("~/Music", None), PathBuf::from("~/Music"));
+    }
+
+    #[test]
+    fn test_split_output_template_keeps_fixed_prefix() {
+        let (base, rest) = split_output_template(Path::new("/music/{artist}/{album}"));
+        assert_eq!(base, PathBuf::from("/music"));
+        assert_eq!(rest.as_deref(), Some("{artist}/{album}"));
+        let (base, rest) = split_output_template(Path::new("/music/incoming"));
+        assert_eq!(base, PathBuf::from("/music/incoming"));
+        assert_eq!(rest, None);
+    }
+}
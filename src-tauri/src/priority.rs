@@ -0,0 +1,88 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Pause after each file of a low priority scan, leaving the CPU to other programs
+pub const LOW_PRIORITY_PAUSE: Duration = Duration::from_millis(50);
+
+/// Nice value given to scan threads in low priority mode
+#[cfg(unix)]
+const LOW_NICE: libc::c_int = 10;
+
+thread_local! {
+    static LOWERED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Lower the calling scan thread's priority, once per thread. Analyzer processes it
+/// spawns afterwards inherit the lower priority. Linux keeps nice values per thread;
+/// on other Unix systems setpriority applies to the whole process. Either way an
+/// unprivileged process can't raise it back, so it lasts until restart. On Windows the
+/// priority is handled process-wide by LowPriorityScope instead.
+pub fn lower_current_thread() {
+    if LOWERED.with(|l| l.replace(true)) {
+        return;
+    }
+    #[cfg(unix)]
+    {
+        // SAFETY: plain syscall on the calling thread/process, no pointers involved
+        let rc = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, LOW_NICE) };
+        if rc != 0 {
+            log::warn!(
+                "[priority] setpriority failed: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+#[cfg(windows)]
+mod win {
+    use std::ffi::c_void;
+
+    pub const NORMAL_PRIORITY_CLASS: u32 = 0x0000_0020;
+    pub const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetCurrentProcess() -> *mut c_void;
+        pub fn SetPriorityClass(process: *mut c_void, class: u32) -> i32;
+    }
+
+    pub fn set_priority_class(class: u32) {
+        // SAFETY: GetCurrentProcess returns a pseudo handle that needs no closing
+        let ok = unsafe { SetPriorityClass(GetCurrentProcess(), class) };
+        if ok == 0 {
+            log::warn!(
+                "[priority] SetPriorityClass failed: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Low priority scans in progress; the process class is restored when the last ends
+static LOW_SCOPES: AtomicUsize = AtomicUsize::new(0);
+
+/// Keeps the process in the below-normal priority class on Windows while alive.
+/// Nothing to do on Unix, where scan threads lower themselves.
+pub struct LowPriorityScope(());
+
+impl LowPriorityScope {
+    pub fn enter() -> Self {
+        if LOW_SCOPES.fetch_add(1, Ordering::SeqCst) == 0 {
+            log::info!("[priority] Scanning at low priority");
+            #[cfg(windows)]
+            win::set_priority_class(win::BELOW_NORMAL_PRIORITY_CLASS);
+        }
+        LowPriorityScope(())
+    }
+}
+
+impl Drop for LowPriorityScope {
+    fn drop(&mut self) {
+        if LOW_SCOPES.fetch_sub(1, Ordering::SeqCst) == 1 {
+            #[cfg(windows)]
+            win::set_priority_class(win::NORMAL_PRIORITY_CLASS);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// Child processes (analyzer runs, ffprobe calls, analyzer workers) started by the app.
+// They are tracked by pid while running so an exit mid-scan can kill them instead of
+// leaving them orphaned.
+
+use std::collections::HashSet;
+use std::io;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+fn running() -> &'static Mutex<HashSet<u32>> {
+    static RUNNING: OnceLock<Mutex<HashSet<u32>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+pub fn track(pid: u32) {
+    running().lock().unwrap_or_else(|e| e.into_inner()).insert(pid);
+}
+
+pub fn untrack(pid: u32) {
+    running().lock().unwrap_or_else(|e| e.into_inner()).remove(&pid);
+}
+
+/// Like `Command::output`, but the child is tracked until it exits
+pub fn tracked_output(cmd: &mut Command) -> io::Result<Output> {
+    let child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let pid = child.id();
+    track(pid);
+    let output = child.wait_with_output();
+    untrack(pid);
+    output
+}
+
+/// Kill every tracked child (app shutdown)
+pub fn kill_all() {
+    let pids: Vec<u32> = running()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain()
+        .collect();
+    for pid in pids {
+        log::info!("[processes] Killing child process {}", pid);
+        #[cfg(target_os = "windows")]
+        let result = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .status();
+        #[cfg(not(target_os = "windows"))]
+        let result = Command::new("kill").args(["-9", &pid.to_string()]).status();
+        if let Err(e) = result {
+            log::warn!("[processes] Failed to kill {}: {}", pid, e);
+        }
+    }
+}
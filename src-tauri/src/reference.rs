@@ -0,0 +1,196 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::audio::probe_duration;
+use crate::settings::load_settings;
+use crate::CORE_API_URL;
+
+/// Durations closer than this many seconds (or DURATION_TOLERANCE_RATIO of the reference)
+/// are taken for the same recording
+const DURATION_TOLERANCE_SECS: f64 = 3.0;
+const DURATION_TOLERANCE_RATIO: f64 = 0.02;
+
+/// Best quality the core service knows for an ISRC, returned by quality_reference
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct QualityReference {
+    pub isrc: String,
+    /// False when the service could not be reached; everything below is then unknown
+    pub online: bool,
+    pub found: bool,
+    /// "tidal" or "soundcloud"
+    pub source: Option<String>,
+    pub lossless_available: Option<bool>,
+    /// Duration of the reference recording, in seconds
+    pub reference_duration: Option<f64>,
+    /// Duration of the local file, when a path was given
+    pub local_duration: Option<f64>,
+    /// Whether both durations agree; false hints that the ISRC matched another recording
+    pub duration_matches: Option<bool>,
+}
+
+/// Uppercase the ISRC and drop the dashes and spaces it is often printed with
+fn normalize_isrc(isrc: &str) -> Option<String> {
+    let code: String = isrc
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+    (code.len() == 12 && code.chars().all(|c| c.is_ascii_alphanumeric())).then_some(code)
+}
+
+fn durations_match(local: f64, reference: f64) -> bool {
+    (local - reference).abs() <= DURATION_TOLERANCE_SECS.max(reference * DURATION_TOLERANCE_RATIO)
+}
+
+/// Whether a Tidal audio quality ("LOSSLESS", "HI_RES_LOSSLESS", "HIGH"...) is lossless
+fn is_lossless_quality(quality: &str) -> bool {
+    matches!(
+        quality.to_ascii_uppercase().as_str(),
+        "LOSSLESS" | "HI_RES" | "HI_RES_LOSSLESS"
+    )
+}
+
+/// Read a /search/track answer. Lossless availability comes from the service's
+/// `lossless` flag, else the matched track's quality; SoundCloud streams are lossy.
+/// Unknown when a Tidal match reports neither.
+fn reference_from_response(
+    isrc: String,
+    json: &serde_json::Value,
+    local_duration: Option<f64>,
+) -> QualityReference {
+    let found = json["success"].as_bool().unwrap_or(false) && json["found"].as_bool().unwrap_or(false);
+    if !found {
+        return QualityReference {
+            isrc,
+            online: true,
+            local_duration,
+            ..Default::default()
+        };
+    }
+    let source = json["source"].as_str().map(|s| s.to_string());
+    let quality = json["quality"].as_str().or_else(|| json["audioQuality"].as_str());
+    let lossless_available = json["lossless"]
+        .as_bool()
+        .or_else(|| quality.map(is_lossless_quality))
+        .or_else(|| (source.as_deref() == Some("soundcloud")).then_some(false));
+    let reference_duration = json["duration"].as_f64().filter(|d| *d > 0.0);
+    QualityReference {
+        isrc,
+        online: true,
+        found: true,
+        source,
+        lossless_available,
+        reference_duration,
+        local_duration,
+        duration_matches: local_duration
+            .zip(reference_duration)
+            .map(|(local, reference)| durations_match(local, reference)),
+    }
+}
+
+/// Ask the core service for the best available version of `isrc`, and compare its
+/// duration with the file at `path` when one is given. An unreachable service is not
+/// an error: the answer comes back with `online: false`.
+#[tauri::command]
+pub async fn quality_reference(
+    isrc: String,
+    path: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<QualityReference, String> {
+    let isrc = normalize_isrc(&isrc).ok_or_else(|| format!("ISRC invalide: {}", isrc))?;
+    let client_token = load_settings(&app)
+        .client_token
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| "Non enregistré. Veuillez entrer votre code d'invitation.".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let local_duration = path
+            .as_deref()
+            .map(Path::new)
+            .filter(|p| p.is_file())
+            .and_then(|p| probe_duration(p, &app));
+
+        let offline = |e: String| {
+            log::warn!("[reference] Quality reference for {} unavailable: {}", isrc, e);
+            Ok(QualityReference {
+                isrc: isrc.clone(),
+                local_duration,
+                ..Default::default()
+            })
+        };
+        let client = match reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => return offline(e.to_string()),
+        };
+        let payload = serde_json::json!({
+            "query": isrc,
+            "metadata": { "isrc": isrc },
+            "source": "auto"
+        });
+        let resp = match client
+            .post(format!("{}/search/track", CORE_API_URL))
+            .header("X-Client-Token", &client_token)
+            .json(&payload)
+            .send()
+        {
+            Ok(resp) => resp,
+            Err(e) => return offline(e.to_string()),
+        };
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err("AUTH_REQUIRED: Session expirée ou invalide. Veuillez vous réenregistrer.".to_string());
+        }
+        if !resp.status().is_success() {
+            return offline(format!("HTTP {}", resp.status()));
+        }
+        match resp.json::<serde_json::Value>() {
+            Ok(json) => Ok(reference_from_response(isrc.clone(), &json, local_duration)),
+            Err(e) => offline(e.to_string()),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_flags_lossless_and_duration_mismatch() {
+        assert_eq!(normalize_isrc("us-rc1-23 45678").as_deref(), Some("USRC12345678"));
+        assert_eq!(normalize_isrc("USRC1234"), None);
+
+        let tidal = serde_json::json!({
+            "success": true, "found": true, "source": "tidal", "duration": 200.0,
+            "quality": "LOSSLESS"
+        });
+        let reference = reference_from_response("USRC12345678".into(), &tidal, Some(201.5));
+        assert_eq!(reference.lossless_available, Some(true));
+        assert_eq!(reference.duration_matches, Some(true));
+
+        // A Tidal match isn't lossless by itself: a lossy-only track or a missing
+        // quality must not be reported as lossless
+        let tidal_high = serde_json::json!({
+            "success": true, "found": true, "source": "tidal", "quality": "HIGH"
+        });
+        let reference = reference_from_response("USRC12345678".into(), &tidal_high, None);
+        assert_eq!(reference.lossless_available, Some(false));
+        let tidal_unknown = serde_json::json!({ "success": true, "found": true, "source": "tidal" });
+        let reference = reference_from_response("USRC12345678".into(), &tidal_unknown, None);
+        assert_eq!(reference.lossless_available, None);
+
+        let soundcloud = serde_json::json!({
+            "success": true, "found": true, "source": "soundcloud", "duration": 200.0
+        });
+        let reference = reference_from_response("USRC12345678".into(), &soundcloud, Some(260.0));
+        assert_eq!(reference.lossless_available, Some(false));
+        assert_eq!(reference.duration_matches, Some(false));
+
+        let missing = serde_json::json!({ "success": true, "found": false });
+        let reference = reference_from_response("USRC12345678".into(), &missing, None);
+        assert!(reference.online && !reference.found);
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::Path;
+use tauri::Emitter;
+
+use crate::audio::{path_arg, probe_duration, run_ffmpeg_sidecar};
+use crate::tagging::{write_replaygain_tags, ReplayGainTags};
+use crate::types::{BatchProgress, ReplayGainOutcome};
+
+/// ReplayGain 2.0 reference loudness
+const REFERENCE_LUFS: f64 = -18.0;
+
+/// What ffmpeg's ebur128 filter reports for one file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness, in LUFS
+    pub integrated_lufs: f64,
+    /// True peak, in dBFS
+    pub true_peak_dbfs: f64,
+}
+
+/// Pull integrated loudness and true peak out of the ebur128 summary ffmpeg prints on
+/// stderr when the filter finishes
+fn parse_ebur128_summary(stderr: &str) -> Option<Loudness> {
+    let summary = &stderr[stderr.rfind("Summary:")?..];
+    let value_of = |label: &str| {
+        summary.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix(label)?;
+            rest.split_whitespace().next()?.parse::<f64>().ok()
+        })
+    };
+    Some(Loudness {
+        integrated_lufs: value_of("I:")?,
+        true_peak_dbfs: value_of("Peak:")?,
+    })
+}
+
+/// Measure one file with ffmpeg's ebur128 filter (first audio stream, true peak on)
+pub fn measure(path: &Path, app: &tauri::AppHandle) -> Result<Loudness, String> {
+    let path_str = path_arg(path)?;
+    let args = vec![
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        path_str,
+        "-map",
+        "0:a:0",
+        "-af",
+        "ebur128=peak=true",
+        "-f",
+        "null",
+        "-",
+    ];
+    let stderr = run_ffmpeg_sidecar(app, args)?;
+    let loudness = parse_ebur128_summary(&String::from_utf8_lossy(&stderr))
+        .ok_or_else(|| "Mesure de niveau sonore illisible".to_string())?;
+    // Silence measures as -70 LUFS (or -inf); a gain from it would be meaningless
+    if !loudness.integrated_lufs.is_finite() || loudness.integrated_lufs <= -70.0 {
+        return Err("Fichier silencieux, gain non calculable".into());
+    }
+    Ok(loudness)
+}
+
+fn gain_for(integrated_lufs: f64) -> f64 {
+    REFERENCE_LUFS - integrated_lufs
+}
+
+fn linear_peak(true_peak_dbfs: f64) -> f64 {
+    10f64.powf(true_peak_dbfs / 20.0)
+}
+
+/// Album loudness from per-track loudness, weighting each track's energy by its
+/// duration. Close to measuring the concatenated tracks, but ignores how the gating
+/// would change across track boundaries. A track of unknown duration weighs as much
+/// as the average track whose duration is known (all weigh the same if none is).
+fn album_loudness(tracks: &[(Loudness, Option<f64>)]) -> Option<Loudness> {
+    let known: Vec<f64> = tracks.iter().filter_map(|(_, secs)| *secs).collect();
+    let fallback = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+    let weight = |secs: &Option<f64>| secs.unwrap_or(fallback);
+    let total: f64 = tracks.iter().map(|(_, secs)| weight(secs)).sum();
+    if tracks.is_empty() || total <= 0.0 {
+        return None;
+    }
+    let energy: f64 = tracks
+        .iter()
+        .map(|(l, secs)| weight(secs) * 10f64.powf(l.integrated_lufs / 10.0))
+        .sum();
+    Some(Loudness {
+        integrated_lufs: 10.0 * (energy / total).log10(),
+        true_peak_dbfs: tracks
+            .iter()
+            .map(|(l, _)| l.true_peak_dbfs)
+            .fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+/// Measure each file with ebur128 and write REPLAYGAIN_TRACK_* tags (plus
+/// REPLAYGAIN_ALBUM_* across the measured files in album mode). Files are handled one
+/// after the other, like mark_replaced, and "replaygain_progress" is emitted after each
+/// measurement. A failure only affects its own file; in album mode the album gain comes
+/// from the files that could be measured.
+#[tauri::command]
+pub async fn compute_replaygain(
+    paths: Vec<String>,
+    album_mode: bool,
+    app: tauri::AppHandle,
+) -> Result<Vec<ReplayGainOutcome>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = paths.len();
+        let mut measured = Vec::with_capacity(total);
+        for (i, path) in paths.into_iter().enumerate() {
+            let p = Path::new(&path);
+            let loudness = if p.is_file() {
+                measure(p, &app)
+            } else {
+                Err("Fichier introuvable".to_string())
+            };
+            if let Err(e) = &loudness {
+                log::warn!("[replaygain] Could not measure {:?}: {}", path, e);
+            }
+            let _ = app.emit(
+                "replaygain_progress",
+                BatchProgress {
+                    done: i + 1,
+                    total,
+                    path: path.clone(),
+                },
+            );
+            measured.push((path, loudness));
+        }
+
+        let album = if album_mode {
+            let tracks: Vec<(Loudness, Option<f64>)> = measured
+                .iter()
+                .filter_map(|(path, l)| {
+                    let l = *l.as_ref().ok()?;
+                    let secs = probe_duration(Path::new(path), &app).filter(|d| *d > 0.0);
+                    if secs.is_none() {
+                        log::warn!(
+                            "[replaygain] No duration for {:?}, weighting it as an average track",
+                            path
+                        );
+                    }
+                    Some((l, secs))
+                })
+                .collect();
+            album_loudness(&tracks)
+        } else {
+            None
+        };
+        let album_tags = album.map(|a| (gain_for(a.integrated_lufs), linear_peak(a.true_peak_dbfs)));
+
+        measured
+            .into_iter()
+            .map(|(path, loudness)| {
+                let loudness = match loudness {
+                    Ok(l) => l,
+                    Err(e) => {
+                        return ReplayGainOutcome {
+                            path,
+                            error: Some(e),
+                            ..Default::default()
+                        }
+                    }
+                };
+                let tags = ReplayGainTags {
+                    track_gain: gain_for(loudness.integrated_lufs),
+                    track_peak: linear_peak(loudness.true_peak_dbfs),
+                    album: album_tags,
+                };
+                let written = match write_replaygain_tags(Path::new(&path), &tags) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err("Format non pris en charge pour le tag".to_string()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = &written {
+                    log::warn!("[replaygain] Could not tag {:?}: {}", path, e);
+                }
+                ReplayGainOutcome {
+                    path,
+                    track_gain: Some(tags.track_gain),
+                    album_gain: tags.album.map(|(gain, _)| gain),
+                    tagged: written.is_ok(),
+                    error: written.err(),
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ebur128_summary_and_album_loudness() {
+        let stderr = "[Parsed_ebur128_0 @ 0x1] t: 9.9   M: -15.1 S: -14.8 I: -14.9 LUFS\n\
+            [Parsed_ebur128_0 @ 0x1] Summary:\n\n  Integrated loudness:\n    I:         -14.2 LUFS\n\
+            \x20   Threshold: -24.5 LUFS\n\n  Loudness range:\n    LRA:         6.3 LU\n\n\
+            \x20 True peak:\n    Peak:        0.5 dBFS\n";
+        let loudness = parse_ebur128_summary(stderr).unwrap();
+        assert_eq!(loudness.integrated_lufs, -14.2);
+        assert_eq!(loudness.true_peak_dbfs, 0.5);
+        assert!(parse_ebur128_summary("no summary here").is_none());
+
+        // Two equally long tracks 10 LU apart: the louder one dominates the energy
+        let quiet = Loudness { integrated_lufs: -20.0, true_peak_dbfs: -3.0 };
+        let loud = Loudness { integrated_lufs: -10.0, true_peak_dbfs: -0.5 };
+        let album = album_loudness(&[(quiet, Some(60.0)), (loud, Some(60.0))]).unwrap();
+        assert!((album.integrated_lufs - -12.6).abs() < 0.05);
+        assert_eq!(album.true_peak_dbfs, -0.5);
+        // A track whose duration couldn't be probed counts as an average one, not 1 s
+        let unknown = album_loudness(&[(quiet, Some(60.0)), (loud, None)]).unwrap();
+        assert_eq!(unknown, album);
+        let none_known = album_loudness(&[(quiet, None), (loud, None)]).unwrap();
+        assert!((none_known.integrated_lufs - album.integrated_lufs).abs() < 1e-9);
+        assert!((gain_for(-14.2) - -3.8).abs() < 1e-9);
+    }
+}
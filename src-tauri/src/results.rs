@@ -0,0 +1,585 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::types::{ScanResult, ScanStatus};
+
+/// Results of the last completed scan, queried by the UI in slices
+#[derive(Default)]
+pub struct ResultsState(Mutex<Vec<ScanResult>>);
+
+impl ResultsState {
+    pub fn clear(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn replace(&self, results: Vec<ScanResult>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = results;
+    }
+
+    /// Paths of the last scan's rows with this status, in scan order
+    pub fn paths_with_status(&self, status: ScanStatus) -> Vec<String> {
+        let results = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        results
+            .iter()
+            .filter(|r| r.status == status)
+            .map(|r| r.path.clone())
+            .collect()
+    }
+
+    /// Only bad rows can be marked replaced; files outside the last scan are allowed
+    pub fn check_replaceable(&self, path: &str) -> Result<(), String> {
+        let results = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match results.iter().find(|r| r.path == path) {
+            Some(row) if row.status != ScanStatus::Bad => {
+                Err("Seuls les fichiers de mauvaise qualité peuvent être marqués remplacés".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Flag a bad row of the last scan as replaced once its tag has been written
+    pub fn set_replaced(&self, path: &str) -> Result<(), String> {
+        self.check_replaceable(path)?;
+        let mut results = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(row) = results.iter_mut().find(|r| r.path == path) {
+            row.replaced = true;
+            row.status = ScanStatus::Replaced;
+        }
+        Ok(())
+    }
+
+    /// Swap in a fresh row for a file that is part of the last scan
+    pub fn update(&self, result: ScanResult) {
+        let mut results = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(row) = results.iter_mut().find(|r| r.path == result.path) {
+            *row = result;
+        }
+    }
+}
+
+/// Completed scans kept in results.json, newest first
+const MAX_SAVED_SCANS: usize = 5;
+
+/// A completed scan as persisted to results.json
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedScan {
+    pub root: String,
+    /// RFC 3339 local time the scan finished
+    pub scanned_at: String,
+    pub results: Vec<ScanResult>,
+}
+
+pub fn results_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = crate::settings::data_dir(app);
+    Ok(base.join("results.json"))
+}
+
+fn load_saved_scans(path: &Path) -> Vec<SavedScan> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Prepend a scan to results.json, dropping the oldest beyond MAX_SAVED_SCANS
+pub fn save_scan(path: &Path, scan: SavedScan) -> io::Result<()> {
+    let mut scans = load_saved_scans(path);
+    scans.retain(|s| s.root != scan.root || s.scanned_at != scan.scanned_at);
+    scans.insert(0, scan);
+    scans.truncate(MAX_SAVED_SCANS);
+
+    crate::cache::write_atomic(path, serde_json::to_string(&scans).unwrap_or_default().as_bytes())
+}
+
+/// Persist a finished scan, logging instead of failing the scan on IO errors
+pub fn record_scan(app: &tauri::AppHandle, root: &Path, results: &[ScanResult]) {
+    let scan = SavedScan {
+        root: root.display().to_string(),
+        scanned_at: chrono::Local::now().to_rfc3339(),
+        results: results.to_vec(),
+    };
+    match results_path(app).and_then(|p| save_scan(&p, scan).map_err(|e| e.to_string())) {
+        Ok(_) => log::info!("[results] Saved {} results for {:?}", results.len(), root),
+        Err(e) => log::warn!("[results] Failed to save scan results: {}", e),
+    }
+}
+
+/// Filter, sort and pagination options for query_results
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct ResultsQuery {
+    /// Keep only rows with this status ("ok", "bad", "error", "replaced")
+    pub status: Option<String>,
+    pub min_bitrate: Option<u32>,
+    pub max_bitrate: Option<u32>,
+    /// Case-insensitive substring of the file name
+    pub name: Option<String>,
+    /// "name" (default), "path", "bitrate", "status" or "size"
+    pub sort: Option<String>,
+    pub descending: bool,
+    pub offset: usize,
+    /// Maximum rows returned; None returns everything after `offset`
+    pub limit: Option<usize>,
+}
+
+/// One page of results plus the number of rows matching the filter
+#[derive(Serialize)]
+pub struct ResultsPage {
+    pub total: usize,
+    pub results: Vec<ScanResult>,
+}
+
+fn matches(r: &ScanResult, q: &ResultsQuery, name: Option<&str>) -> bool {
+    if q.status.as_deref().is_some_and(|s| s != r.status.as_str()) {
+        return false;
+    }
+    // Rows without a bitrate never satisfy a bitrate bound
+    if q.min_bitrate.is_some_and(|min| r.bitrate.is_none_or(|b| b < min)) {
+        return false;
+    }
+    if q.max_bitrate.is_some_and(|max| r.bitrate.is_none_or(|b| b > max)) {
+        return false;
+    }
+    match name {
+        Some(needle) => r.name.to_lowercase().contains(needle),
+        None => true,
+    }
+}
+
+fn compare(a: &ScanResult, b: &ScanResult, key: &str) -> Ordering {
+    match key {
+        "path" => a.path.cmp(&b.path),
+        // Rows without a bitrate sort first
+        "bitrate" => a.bitrate.cmp(&b.bitrate),
+        "status" => a.status.as_str().cmp(b.status.as_str()),
+        "size" => a.size_bytes.cmp(&b.size_bytes),
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    }
+    .then_with(|| a.path.cmp(&b.path))
+}
+
+pub fn apply_query(results: &[ScanResult], query: &ResultsQuery) -> ResultsPage {
+    let name = query
+        .name
+        .as_deref()
+        .map(|n| n.trim().to_lowercase())
+        .filter(|n| !n.is_empty());
+    let mut rows: Vec<&ScanResult> = results
+        .iter()
+        .filter(|r| matches(r, query, name.as_deref()))
+        .collect();
+
+    let key = query.sort.as_deref().unwrap_or("name");
+    rows.sort_by(|a, b| {
+        let ord = compare(a, b, key);
+        if query.descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+
+    let total = rows.len();
+    let results = rows
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect();
+    ResultsPage { total, results }
+}
+
+/// Filter, sort and page the last scan's results
+#[tauri::command]
+pub fn query_results(query: ResultsQuery, state: tauri::State<'_, ResultsState>) -> ResultsPage {
+    let results = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    apply_query(&results, &query)
+}
+
+/// Most recent saved scan of `root`, if results.json still holds one
+pub fn last_scan_of(app: &tauri::AppHandle, root: &Path) -> Option<SavedScan> {
+    let root = root.display().to_string();
+    let path = results_path(app).ok()?;
+    load_saved_scans(&path).into_iter().find(|s| s.root == root)
+}
+
+/// Most recent saved scan, if any. Also makes it the set query_results works on.
+#[tauri::command]
+pub fn load_last_scan(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ResultsState>,
+) -> Result<Option<SavedScan>, String> {
+    let path = results_path(&app)?;
+    let last = load_saved_scans(&path).into_iter().next();
+    if let Some(scan) = &last {
+        state.replace(scan.results.clone());
+    }
+    Ok(last)
+}
+
+/// One bar of the bitrate histogram
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HistogramBucket {
+    pub label: String,
+    pub count: usize,
+}
+
+/// Bucket lossy files by estimated bitrate (ascending), followed by "lossless" and
+/// "unknown" (no bitrate) counts when non-zero
+pub fn histogram(results: &[ScanResult], bucket_size: u32) -> Vec<HistogramBucket> {
+    histogram_of(results.iter().map(|r| (r.is_lossless, r.bitrate)), bucket_size)
+}
+
+/// `histogram` over bare (is_lossless, bitrate) pairs, e.g. cache entries
+pub fn histogram_of(
+    items: impl IntoIterator<Item = (Option<bool>, Option<u32>)>,
+    bucket_size: u32,
+) -> Vec<HistogramBucket> {
+    let mut buckets = BTreeMap::new();
+    let mut lossless = 0;
+    let mut unknown = 0;
+
+    for item in items {
+        match item {
+            (Some(true), _) => lossless += 1,
+            (_, Some(b)) => *buckets.entry(b / bucket_size).or_insert(0) += 1,
+            (_, None) => unknown += 1,
+        }
+    }
+
+    let mut out: Vec<HistogramBucket> = buckets
+        .into_iter()
+        .map(|(i, count)| HistogramBucket {
+            label: format!("{}-{} kbps", i * bucket_size, (i * bucket_size).saturating_add(bucket_size - 1)),
+            count,
+        })
+        .collect();
+    if lossless > 0 {
+        out.push(HistogramBucket {
+            label: "lossless".to_string(),
+            count: lossless,
+        });
+    }
+    if unknown > 0 {
+        out.push(HistogramBucket {
+            label: "unknown".to_string(),
+            count: unknown,
+        });
+    }
+    out
+}
+
+/// Bitrate distribution of a result set, for the library overview chart
+#[tauri::command]
+pub fn compute_histogram(
+    results: Vec<ScanResult>,
+    bucket_size_kbps: u32,
+) -> Result<Vec<HistogramBucket>, String> {
+    if bucket_size_kbps == 0 {
+        return Err("Taille de tranche invalide".into());
+    }
+    Ok(histogram(&results, bucket_size_kbps))
+}
+
+/// Aggregate verdict of a scan, for when the rows themselves aren't needed
+#[derive(Serialize, Debug, Default)]
+pub struct ScanSummary {
+    pub files: usize,
+    /// Row count per status ("ok", "bad", "error"...)
+    pub by_status: BTreeMap<String, usize>,
+    pub total_bytes: u64,
+    /// Mean estimated bitrate of the files that have one, in kbps
+    pub average_bitrate: Option<u32>,
+    pub lossless: usize,
+    /// Lossy files passing as better than they are: a lossless container with lossy
+    /// content, or a container bitrate well above the analyzed one
+    pub suspected_transcodes: usize,
+    /// Files left out of the scan by its size band; not counted in `files`
+    pub skipped_by_size: usize,
+}
+
+fn is_suspected_transcode(r: &ScanResult) -> bool {
+    if r.is_lossless != Some(false) {
+        return false;
+    }
+    let lossless_codec = r.codec.as_deref().is_some_and(crate::audio::is_lossless_codec);
+    let inflated = match (r.bitrate, r.container_bitrate) {
+        (Some(analyzed), Some(container)) => {
+            analyzed < container && crate::audio::bitrate_disagreement(analyzed, container).is_some()
+        }
+        _ => false,
+    };
+    lossless_codec || inflated
+}
+
+pub fn summarize(results: &[ScanResult]) -> ScanSummary {
+    let mut summary = ScanSummary {
+        files: results.len(),
+        ..Default::default()
+    };
+    let mut bitrate_sum = 0u64;
+    let mut bitrate_files = 0u64;
+    for r in results {
+        *summary.by_status.entry(r.status.as_str().to_string()).or_insert(0) += 1;
+        summary.total_bytes += r.size_bytes.unwrap_or(0);
+        if r.is_lossless == Some(true) {
+            summary.lossless += 1;
+        }
+        if let Some(b) = r.bitrate {
+            bitrate_sum += b as u64;
+            bitrate_files += 1;
+        }
+        if is_suspected_transcode(r) {
+            summary.suspected_transcodes += 1;
+        }
+    }
+    summary.average_bitrate = (bitrate_files > 0).then(|| (bitrate_sum / bitrate_files) as u32);
+    summary
+}
+
+/// The same file in two scans: same path, or same content hash under a new path
+#[derive(Serialize)]
+pub struct ScanChange {
+    pub before: ScanResult,
+    pub after: ScanResult,
+}
+
+/// What changed between two scans, as returned by diff_scans
+#[derive(Serialize, Default)]
+pub struct ScanDiff {
+    /// Bad before, ok after
+    pub improved: Vec<ScanChange>,
+    /// Ok before, bad after
+    pub regressed: Vec<ScanChange>,
+    /// Tagged KESON_REPLACED since the first scan
+    pub replaced: Vec<ScanChange>,
+    /// Matched by hash under a new path, whatever their status did
+    pub renamed: Vec<ScanChange>,
+    pub appeared: Vec<ScanResult>,
+    pub disappeared: Vec<ScanResult>,
+}
+
+/// Match `after` against `before` by path, then leftovers by hash, and sort the pairs
+/// whose outcome changed. Files matching on both sides with the same outcome are left out.
+pub fn diff_results(before: Vec<ScanResult>, after: Vec<ScanResult>) -> ScanDiff {
+    let mut left: Vec<Option<ScanResult>> = before.into_iter().map(Some).collect();
+    let by_path: HashMap<String, usize> = left
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.as_ref().map(|r| (r.path.clone(), i)))
+        .collect();
+
+    let mut pairs = Vec::new();
+    let mut unmatched = Vec::new();
+    for r in after {
+        match by_path.get(&r.path).and_then(|&i| left[i].take()) {
+            Some(b) => pairs.push((b, r)),
+            None => unmatched.push(r),
+        }
+    }
+
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    for (i, r) in left.iter().enumerate() {
+        if let Some(h) = r.as_ref().and_then(|r| r.hash.clone()) {
+            by_hash.entry(h).or_insert(i);
+        }
+    }
+    let mut diff = ScanDiff::default();
+    for r in unmatched {
+        let moved = r
+            .hash
+            .as_ref()
+            .and_then(|h| by_hash.get(h))
+            .and_then(|&i| left[i].take());
+        match moved {
+            Some(b) => pairs.push((b, r)),
+            None => diff.appeared.push(r),
+        }
+    }
+    diff.disappeared = left.into_iter().flatten().collect();
+
+    for (before, after) in pairs {
+        let list = if after.replaced && !before.replaced {
+            &mut diff.replaced
+        } else if before.status == ScanStatus::Bad && after.status == ScanStatus::Ok {
+            &mut diff.improved
+        } else if before.status == ScanStatus::Ok && after.status == ScanStatus::Bad {
+            &mut diff.regressed
+        } else if before.path != after.path {
+            &mut diff.renamed
+        } else {
+            continue;
+        };
+        list.push(ScanChange { before, after });
+    }
+    diff
+}
+
+/// Compare two scans, e.g. before and after a cleanup session
+#[tauri::command]
+pub fn diff_scans(before: Vec<ScanResult>, after: Vec<ScanResult>) -> ScanDiff {
+    diff_results(before, after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, status: ScanStatus, bitrate: Option<u32>) -> ScanResult {
+        ScanResult {
+            path: format!("/music/{}", name),
+            name: name.to_string(),
+            status,
+            bitrate,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_set_replaced_only_flips_bad_rows() {
+        let state = ResultsState::default();
+        state.replace(vec![
+            row("a.mp3", ScanStatus::Bad, Some(128)),
+            row("b.mp3", ScanStatus::Error, None),
+        ]);
+
+        assert!(state.set_replaced("/music/a.mp3").is_ok());
+        assert!(state.set_replaced("/music/b.mp3").is_err());
+        assert!(state.set_replaced("/elsewhere/c.mp3").is_ok());
+        assert_eq!(state.paths_with_status(ScanStatus::Replaced), vec!["/music/a.mp3"]);
+        assert_eq!(state.paths_with_status(ScanStatus::Error), vec!["/music/b.mp3"]);
+    }
+
+    #[test]
+    fn test_apply_query_filters_sorts_and_pages() {
+        let results = vec![
+            row("b.mp3", ScanStatus::Bad, Some(128)),
+            row("a.mp3", ScanStatus::Ok, Some(320)),
+            row("c.mp3", ScanStatus::Bad, Some(96)),
+            row("d.flac", ScanStatus::Error, None),
+        ];
+
+        let page = apply_query(
+            &results,
+            &ResultsQuery {
+                status: Some("bad".into()),
+                sort: Some("bitrate".into()),
+                descending: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(page.total, 2);
+        assert_eq!(page.results[0].name, "b.mp3");
+
+        let page = apply_query(
+            &results,
+            &ResultsQuery {
+                min_bitrate: Some(100),
+                offset: 1,
+                limit: Some(1),
+                ..Default::default()
+            },
+        );
+        assert_eq!(page.total, 2);
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.results[0].name, "b.mp3");
+
+        let page = apply_query(
+            &results,
+            &ResultsQuery {
+                name: Some("FLAC".into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(page.total, 1);
+    }
+
+    #[test]
+    fn test_histogram_buckets_lossy_and_counts_lossless_and_unknown() {
+        let mut lossless = row("e.flac", ScanStatus::Ok, None);
+        lossless.is_lossless = Some(true);
+        let results = vec![
+            row("a.mp3", ScanStatus::Ok, Some(320)),
+            row("b.mp3", ScanStatus::Bad, Some(128)),
+            row("c.mp3", ScanStatus::Bad, Some(130)),
+            row("d.mp3", ScanStatus::Error, None),
+            lossless,
+        ];
+
+        let labels: Vec<(String, usize)> = histogram(&results, 64)
+            .into_iter()
+            .map(|b| (b.label, b.count))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("128-191 kbps".to_string(), 2),
+                ("320-383 kbps".to_string(), 1),
+                ("lossless".to_string(), 1),
+                ("unknown".to_string(), 1),
+            ]
+        );
+        assert!(histogram(&[], 64).is_empty());
+    }
+
+    #[test]
+    fn test_summarize_counts_statuses_bitrates_and_transcodes() {
+        let mut fake_flac = row("a.flac", ScanStatus::Bad, Some(128));
+        fake_flac.is_lossless = Some(false);
+        fake_flac.codec = Some("flac".into());
+        fake_flac.size_bytes = Some(30);
+        let mut inflated = row("b.mp3", ScanStatus::Bad, Some(128));
+        inflated.is_lossless = Some(false);
+        inflated.container_bitrate = Some(320);
+        inflated.size_bytes = Some(10);
+        let mut lossless = row("c.flac", ScanStatus::Ok, None);
+        lossless.is_lossless = Some(true);
+
+        let summary = summarize(&[fake_flac, inflated, lossless, row("d.mp3", ScanStatus::Ok, Some(320))]);
+        assert_eq!(summary.files, 4);
+        assert_eq!(summary.by_status.get("bad"), Some(&2));
+        assert_eq!(summary.by_status.get("ok"), Some(&2));
+        assert_eq!(summary.total_bytes, 40);
+        assert_eq!(summary.average_bitrate, Some(192));
+        assert_eq!(summary.lossless, 1);
+        assert_eq!(summary.suspected_transcodes, 2);
+    }
+
+    #[test]
+    fn test_diff_matches_by_path_then_hash() {
+        let hashed = |name: &str, status, hash: &str| ScanResult {
+            hash: Some(hash.to_string()),
+            ..row(name, status, None)
+        };
+        let before = vec![
+            row("fixed.mp3", ScanStatus::Bad, Some(128)),
+            row("broke.mp3", ScanStatus::Ok, Some(320)),
+            row("same.mp3", ScanStatus::Ok, Some(320)),
+            hashed("old name.mp3", ScanStatus::Ok, "abc"),
+            row("deleted.mp3", ScanStatus::Bad, Some(96)),
+        ];
+        let after = vec![
+            row("fixed.mp3", ScanStatus::Ok, Some(320)),
+            row("broke.mp3", ScanStatus::Bad, Some(128)),
+            row("same.mp3", ScanStatus::Ok, Some(320)),
+            hashed("new name.mp3", ScanStatus::Ok, "abc"),
+            row("new.mp3", ScanStatus::Ok, Some(256)),
+        ];
+        let diff = diff_results(before, after);
+        assert_eq!(diff.improved.len(), 1);
+        assert_eq!(diff.improved[0].after.name, "fixed.mp3");
+        assert_eq!(diff.regressed[0].before.name, "broke.mp3");
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed[0].before.name, "old name.mp3");
+        assert_eq!(diff.appeared.len(), 1);
+        assert_eq!(diff.appeared[0].name, "new.mp3");
+        assert_eq!(diff.disappeared.len(), 1);
+        assert_eq!(diff.disappeared[0].name, "deleted.mp3");
+        assert!(diff.replaced.is_empty());
+    }
+}
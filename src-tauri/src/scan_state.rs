@@ -0,0 +1,307 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audio::SCAN_CANCELLED;
+
+/// What the scan pipeline is doing, as reported by scan_state
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanPhase {
+    Idle,
+    Discovering,
+    Analyzing,
+    /// A scan is still running but has been asked to stop
+    Cancelling,
+}
+
+impl ScanPhase {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ScanPhase::Discovering,
+            2 => ScanPhase::Analyzing,
+            _ => ScanPhase::Idle,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            ScanPhase::Idle | ScanPhase::Cancelling => 0,
+            ScanPhase::Discovering => 1,
+            ScanPhase::Analyzing => 2,
+        }
+    }
+}
+
+/// Snapshot returned by scan_state
+#[derive(Serialize, Debug)]
+pub struct ScanStateReport {
+    pub phase: ScanPhase,
+    /// Files analyzed so far, or found so far while discovering
+    pub files_done: usize,
+    /// Files to analyze; 0 while discovering
+    pub files_total: usize,
+    /// Paths being analyzed right now, one per busy worker thread
+    pub current: Vec<String>,
+    /// Bytes of each `current` file hashed so far, in the same order, so a long hash
+    /// of a big file shows progress
+    pub bytes_hashed: Vec<u64>,
+    /// Seconds since the current phase began; None when idle
+    pub elapsed_secs: Option<f64>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Progress of the running scan, kept in atomics so scan threads only take a lock
+/// (briefly) when they start or finish a file
+#[derive(Default)]
+pub struct ScanTracker {
+    phase: AtomicU8,
+    done: AtomicUsize,
+    total: AtomicUsize,
+    /// Unix time in ms when the phase began
+    started_ms: AtomicU64,
+    /// File each busy thread is analyzing, in the order they started. Keyed by thread
+    /// id so files analyzed off the rayon pool don't share a slot with worker 0.
+    current: Mutex<Vec<(ThreadId, InFlight)>>,
+    /// Set while a scan command holds the RunningScan guard
+    running: AtomicBool,
+    /// Held while `running` and SCAN_CANCELLED change together, so a cancel can't land
+    /// after its scan has ended
+    cancel_lock: Mutex<()>,
+}
+
+/// A file being analyzed and how much of it has been hashed
+struct InFlight {
+    path: String,
+    hashed: Arc<AtomicU64>,
+}
+
+/// Held by the scan command that is running; released on drop, including on error
+pub struct RunningScan<'a>(&'a ScanTracker);
+
+impl Drop for RunningScan<'_> {
+    fn drop(&mut self) {
+        // A cancel request ends with its scan, so it can't interrupt the hashing of
+        // folder-watch updates afterwards
+        let _lock = self.0.cancel_lock.lock().unwrap_or_else(|e| e.into_inner());
+        SCAN_CANCELLED.store(false, Ordering::SeqCst);
+        self.0.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Puts the tracker back to idle when the phase it was handed for ends
+pub struct PhaseGuard<'a>(&'a ScanTracker);
+
+impl Drop for PhaseGuard<'_> {
+    fn drop(&mut self) {
+        self.0.phase.store(ScanPhase::Idle.as_u8(), Ordering::SeqCst);
+        self.0.current.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl ScanTracker {
+    /// Claim the scan slot: scans share the cache file, the progress events and this
+    /// tracker, so only one may run. None when another scan holds it.
+    pub fn try_start(&self) -> Option<RunningScan<'_>> {
+        let _lock = self.cancel_lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()?;
+        // Whatever a previous run left behind must not cancel this one
+        SCAN_CANCELLED.store(false, Ordering::SeqCst);
+        Some(RunningScan(self))
+    }
+
+    /// Ask the running scan to stop; false, leaving everything alone, when none is
+    pub fn request_cancel(&self) -> bool {
+        let _lock = self.cancel_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.running.load(Ordering::SeqCst) {
+            return false;
+        }
+        SCAN_CANCELLED.store(true, Ordering::SeqCst);
+        true
+    }
+
+    pub fn begin(&self, phase: ScanPhase, total: usize) -> PhaseGuard<'_> {
+        self.done.store(0, Ordering::SeqCst);
+        self.total.store(total, Ordering::SeqCst);
+        self.started_ms.store(now_ms(), Ordering::SeqCst);
+        self.phase.store(phase.as_u8(), Ordering::SeqCst);
+        PhaseGuard(self)
+    }
+
+    pub fn set_done(&self, done: usize) {
+        self.done.store(done, Ordering::Relaxed);
+    }
+
+    fn set_current(&self, path: Option<&Path>) {
+        let thread = std::thread::current().id();
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        current.retain(|(id, _)| *id != thread);
+        if let Some(p) = path {
+            current.push((
+                thread,
+                InFlight {
+                    path: p.display().to_string(),
+                    hashed: Arc::default(),
+                },
+            ));
+        }
+    }
+
+    pub fn file_started(&self, path: &Path) {
+        self.set_current(Some(path));
+    }
+
+    pub fn file_finished(&self) {
+        self.set_current(None);
+    }
+
+    /// Counter for the bytes hashed of the file this thread is analyzing. None on
+    /// threads that aren't analyzing a file.
+    pub fn hash_progress(&self) -> Option<Arc<AtomicU64>> {
+        let thread = std::thread::current().id();
+        let current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        current
+            .iter()
+            .find(|(id, _)| *id == thread)
+            .map(|(_, f)| Arc::clone(&f.hashed))
+    }
+
+    pub fn report(&self) -> ScanStateReport {
+        let phase = ScanPhase::from_u8(self.phase.load(Ordering::SeqCst));
+        if phase == ScanPhase::Idle {
+            return ScanStateReport {
+                phase,
+                files_done: 0,
+                files_total: 0,
+                current: Vec::new(),
+                bytes_hashed: Vec::new(),
+                elapsed_secs: None,
+            };
+        }
+        let started = self.started_ms.load(Ordering::SeqCst);
+        let (current, bytes_hashed) = self
+            .current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, f)| (f.path.clone(), f.hashed.load(Ordering::Relaxed)))
+            .unzip();
+        ScanStateReport {
+            phase: if SCAN_CANCELLED.load(Ordering::SeqCst) {
+                ScanPhase::Cancelling
+            } else {
+                phase
+            },
+            files_done: self.done.load(Ordering::Relaxed),
+            files_total: self.total.load(Ordering::SeqCst),
+            current,
+            bytes_hashed,
+            elapsed_secs: Some(now_ms().saturating_sub(started) as f64 / 1000.0),
+        }
+    }
+}
+
+/// Current phase, progress and in-flight files of the scan, for diagnosing a scan that
+/// seems stuck
+#[tauri::command]
+pub fn scan_state(tracker: tauri::State<'_, ScanTracker>) -> ScanStateReport {
+    tracker.report()
+}
+
+/// Stop the running scan: files not started yet come back as "cancelled", the ones
+/// being analyzed finish. Returns false, doing nothing, when no scan is running.
+#[tauri::command]
+pub fn cancel_scan(tracker: tauri::State<'_, ScanTracker>) -> bool {
+    let cancelled = tracker.request_cancel();
+    if cancelled {
+        log::info!("[scan] Cancel requested");
+    }
+    cancelled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_reports_in_flight_files_until_the_phase_ends() {
+        let tracker = ScanTracker::default();
+        assert_eq!(tracker.report().phase, ScanPhase::Idle);
+        {
+            let _phase = tracker.begin(ScanPhase::Analyzing, 3);
+            tracker.file_started(Path::new("/music/a.flac"));
+            tracker.set_done(1);
+            let report = tracker.report();
+            assert_eq!(report.phase, ScanPhase::Analyzing);
+            assert_eq!((report.files_done, report.files_total), (1, 3));
+            assert_eq!(report.current, vec!["/music/a.flac".to_string()]);
+            assert!(report.elapsed_secs.is_some());
+
+            tracker.file_finished();
+            assert!(tracker.report().current.is_empty());
+        }
+        let report = tracker.report();
+        assert_eq!(report.phase, ScanPhase::Idle);
+        assert_eq!(report.elapsed_secs, None);
+    }
+
+    #[test]
+    fn test_only_one_scan_runs_at_a_time() {
+        let tracker = ScanTracker::default();
+        let first = tracker.try_start().expect("first scan starts");
+        assert!(tracker.try_start().is_none());
+        drop(first);
+        assert!(tracker.try_start().is_some());
+    }
+
+    #[test]
+    fn test_cancel_is_a_no_op_without_a_running_scan() {
+        // Cancelling a running scan raises the process-wide SCAN_CANCELLED, which the
+        // other tests here read, so only the idle cases are checked
+        let tracker = ScanTracker::default();
+        assert!(!tracker.request_cancel());
+        drop(tracker.try_start().expect("scan starts"));
+        assert!(!tracker.request_cancel());
+    }
+
+    #[test]
+    fn test_tracker_reports_bytes_hashed_of_in_flight_files() {
+        let tracker = ScanTracker::default();
+        let _phase = tracker.begin(ScanPhase::Analyzing, 1);
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        pool.install(|| {
+            assert!(tracker.hash_progress().is_none());
+            tracker.file_started(Path::new("/music/big.flac"));
+            tracker.hash_progress().expect("in-flight file").fetch_add(4096, Ordering::Relaxed);
+            assert_eq!(tracker.report().bytes_hashed, vec![4096]);
+            tracker.file_finished();
+            assert!(tracker.hash_progress().is_none());
+        });
+        assert!(tracker.report().bytes_hashed.is_empty());
+        // Threads outside the scan's pool have no file of their own
+        assert!(tracker.hash_progress().is_none());
+    }
+
+    #[test]
+    fn test_files_started_off_the_pool_do_not_overwrite_worker_zero() {
+        let tracker = ScanTracker::default();
+        let _phase = tracker.begin(ScanPhase::Analyzing, 2);
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        pool.install(|| tracker.file_started(Path::new("/music/a.flac")));
+        tracker.file_started(Path::new("/music/b.flac"));
+        assert_eq!(tracker.report().current.len(), 2);
+        tracker.file_finished();
+        assert_eq!(tracker.report().current, vec!["/music/a.flac".to_string()]);
+    }
+}
@@ -1,57 +1,717 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tauri::Manager;
 
+const MIN_BITRATE_RANGE: RangeInclusive<u32> = 32..=2000;
+const ANALYSIS_WINDOW_RANGE: RangeInclusive<u32> = 5..=600;
+const CACHE_MAX_ENTRIES_RANGE: RangeInclusive<usize> = 0..=1_000_000;
+const ANALYZER_RETRIES_RANGE: RangeInclusive<u32> = 0..=5;
+const MAX_CONCURRENT_ANALYSES_RANGE: RangeInclusive<usize> = 0..=64;
+const ANALYSIS_OFFSET_RANGE: RangeInclusive<u32> = 0..=600;
+const ANALYSIS_WINDOW_PERCENT_RANGE: RangeInclusive<u32> = 1..=100;
+const DOWNLOAD_RATE_LIMIT_RANGE: RangeInclusive<u32> = 0..=1_000_000;
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+const SCAN_PRIORITIES: [&str; 2] = ["low", "normal"];
+const UNVERSIONED_CACHE_POLICIES: [&str; 2] = ["accept", "reanalyze"];
+const ANALYZER_MODES: [&str; 3] = ["auto", "binary", "python"];
+const ANALYSIS_WINDOW_MODES: [&str; 2] = ["fixed", "fraction"];
+const DEFAULT_AUDIO_EXTENSIONS: [&str; 13] = [
+    "mp3", "m4a", "aac", "wav", "flac", "ogg", "opus", "webm", "aiff", "aif", "wv", "mpc", "ape",
+];
+const DEFAULT_VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "m4v", "mkv", "mov", "avi"];
+/// Formats added to the defaults in settings v2
+const V2_AUDIO_EXTENSIONS: [&str; 5] = ["aiff", "aif", "wv", "mpc", "ape"];
+
+/// Current settings.json schema version. Bump it and extend `upgrade` when fields change shape.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Thresholds applied to files under a specific folder instead of the global values
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FolderOverride {
+    /// Folder prefix the override applies to
+    pub path: String,
+    #[serde(default)]
+    pub min_bitrate: Option<u32>,
+    #[serde(default)]
+    pub analysis_window_seconds: Option<u32>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Settings {
+    /// Schema version; files written before versioning deserialize as 0
+    #[serde(default)]
+    pub version: u32,
     pub min_bitrate: u32,
     pub analysis_window_seconds: u32,
+    /// Seconds skipped before the analysis window, to get past silent intros
+    #[serde(default)]
+    pub analysis_offset_seconds: u32,
+    /// "fixed" analyzes `analysis_window_seconds` of every file, "fraction" analyzes
+    /// `analysis_window_percent` of its duration, between the min and max seconds below
+    #[serde(default = "default_analysis_window_mode")]
+    pub analysis_window_mode: String,
+    #[serde(default = "default_analysis_window_percent")]
+    pub analysis_window_percent: u32,
+    #[serde(default = "default_analysis_window_min_seconds")]
+    pub analysis_window_min_seconds: u32,
+    #[serde(default = "default_analysis_window_max_seconds")]
+    pub analysis_window_max_seconds: u32,
     pub rayon_threads: usize,
     pub cache_enabled: bool,
     pub cache_max_entries: usize,
     /// Client token received after registration with the Core server
     #[serde(default)]
     pub client_token: Option<String>,
+    /// Per-folder thresholds; the most specific matching folder wins
+    #[serde(default)]
+    pub folder_overrides: Vec<FolderOverride>,
+    /// Extra attempts after a transient analyzer failure
+    #[serde(default = "default_analyzer_retries")]
+    pub analyzer_retries: u32,
+    /// Analyzer processes allowed to run at once, whatever the thread count; librosa
+    /// is memory-hungry. 0 = half the CPUs
+    #[serde(default)]
+    pub max_concurrent_analyses: usize,
+    /// Minimum level written to the log file: error, warn, info, debug or trace
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// File extensions treated as audio by scans and folder watching (case-insensitive)
+    #[serde(default = "default_audio_extensions")]
+    pub audio_extensions: Vec<String>,
+    /// Also scan video containers, analyzing their first audio stream
+    #[serde(default)]
+    pub include_video: bool,
+    /// Extensions treated as video containers when `include_video` is on
+    #[serde(default = "default_video_extensions")]
+    pub video_extensions: Vec<String>,
+    /// Leave out dotfiles, AppleDouble `._` files and hidden/system files when scanning
+    #[serde(default = "default_skip_hidden")]
+    pub skip_hidden: bool,
+    /// Don't analyze files already tagged KESON_REPLACED; they're listed as "replaced"
+    #[serde(default)]
+    pub skip_replaced: bool,
+    /// Write each fresh analysis into the file as a KESON_ANALYSIS comment, read back
+    /// when the hash cache misses. Modifies the files, so off by default.
+    #[serde(default)]
+    pub embed_analysis: bool,
+    /// Judge files from ffprobe first: lossless codecs and container bitrates well above
+    /// the threshold are marked "ok" without spectral analysis, so fake lossless files
+    /// and upscaled transcodes among them go unnoticed
+    #[serde(default)]
+    pub quick_prefilter: bool,
+    /// Decode each analyzed file once more with ffmpeg's volumedetect to flag peaks at
+    /// 0 dBFS. Roughly doubles the time spent per file, so off by default.
+    #[serde(default)]
+    pub detect_clipping: bool,
+    /// "low" lowers the scan threads' priority and pauses between files, "normal" doesn't
+    #[serde(default = "default_scan_priority")]
+    pub scan_priority: String,
+    /// Cache entries from before analyzer versioning: "accept" keeps using them,
+    /// "reanalyze" treats them as misses
+    #[serde(default = "default_unversioned_cache")]
+    pub unversioned_cache: String,
+    /// Which whatsmybitrate to run: "binary" (bundled), "python" (dev script) or "auto",
+    /// the binary with the script as fallback
+    #[serde(default = "default_analyzer_mode")]
+    pub analyzer_mode: String,
+    /// Absolute folder for the cache and results, used only when the platform data and
+    /// cache dirs can't be resolved (locked-down or portable installs)
+    #[serde(default)]
+    pub data_dir: Option<String>,
+    /// Cap on download speed, in kilobits per second; None or 0 means unlimited
+    #[serde(default)]
+    pub download_rate_limit_kbps: Option<u32>,
+}
+
+fn default_analyzer_retries() -> u32 {
+    2
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_video_extensions() -> Vec<String> {
+    DEFAULT_VIDEO_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+fn default_scan_priority() -> String {
+    "normal".to_string()
+}
+
+fn default_unversioned_cache() -> String {
+    "accept".to_string()
+}
+
+fn default_analyzer_mode() -> String {
+    "auto".to_string()
+}
+
+fn default_analysis_window_mode() -> String {
+    "fixed".to_string()
+}
+
+fn default_analysis_window_percent() -> u32 {
+    30
+}
+
+fn default_analysis_window_min_seconds() -> u32 {
+    20
+}
+
+fn default_analysis_window_max_seconds() -> u32 {
+    120
+}
+
+fn default_skip_hidden() -> bool {
+    true
+}
+
+fn default_audio_extensions() -> Vec<String> {
+    DEFAULT_AUDIO_EXTENSIONS.iter().map(|e| e.to_string()).collect()
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Settings {
+            version: SETTINGS_VERSION,
             min_bitrate: 256,
             analysis_window_seconds: 100,
+            analysis_offset_seconds: 0,
+            analysis_window_mode: default_analysis_window_mode(),
+            analysis_window_percent: default_analysis_window_percent(),
+            analysis_window_min_seconds: default_analysis_window_min_seconds(),
+            analysis_window_max_seconds: default_analysis_window_max_seconds(),
             rayon_threads: 0,
             cache_enabled: true,
             cache_max_entries: 10_000,
             client_token: None,
+            folder_overrides: Vec::new(),
+            analyzer_retries: default_analyzer_retries(),
+            max_concurrent_analyses: 0,
+            log_level: default_log_level(),
+            audio_extensions: default_audio_extensions(),
+            include_video: false,
+            video_extensions: default_video_extensions(),
+            skip_hidden: default_skip_hidden(),
+            skip_replaced: false,
+            embed_analysis: false,
+            quick_prefilter: false,
+            detect_clipping: false,
+            scan_priority: default_scan_priority(),
+            unversioned_cache: default_unversioned_cache(),
+            analyzer_mode: default_analyzer_mode(),
+            data_dir: None,
+            download_rate_limit_kbps: None,
         }
     }
 }
 
-pub fn settings_path(app: &tauri::AppHandle) -> PathBuf {
+impl Settings {
+    /// List every value outside its accepted range
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if !MIN_BITRATE_RANGE.contains(&self.min_bitrate) {
+            errors.push(format!(
+                "min_bitrate={} (attendu {}–{})",
+                self.min_bitrate,
+                MIN_BITRATE_RANGE.start(),
+                MIN_BITRATE_RANGE.end()
+            ));
+        }
+        if !ANALYSIS_WINDOW_RANGE.contains(&self.analysis_window_seconds) {
+            errors.push(format!(
+                "analysis_window_seconds={} (attendu {}–{})",
+                self.analysis_window_seconds,
+                ANALYSIS_WINDOW_RANGE.start(),
+                ANALYSIS_WINDOW_RANGE.end()
+            ));
+        }
+        if !ANALYSIS_WINDOW_MODES.contains(&self.analysis_window_mode.as_str()) {
+            errors.push(format!(
+                "analysis_window_mode={} (attendu {})",
+                self.analysis_window_mode,
+                ANALYSIS_WINDOW_MODES.join(", ")
+            ));
+        }
+        if !ANALYSIS_WINDOW_PERCENT_RANGE.contains(&self.analysis_window_percent) {
+            errors.push(format!(
+                "analysis_window_percent={} (attendu {}–{})",
+                self.analysis_window_percent,
+                ANALYSIS_WINDOW_PERCENT_RANGE.start(),
+                ANALYSIS_WINDOW_PERCENT_RANGE.end()
+            ));
+        }
+        for (name, value) in [
+            ("analysis_window_min_seconds", self.analysis_window_min_seconds),
+            ("analysis_window_max_seconds", self.analysis_window_max_seconds),
+        ] {
+            if !ANALYSIS_WINDOW_RANGE.contains(&value) {
+                errors.push(format!(
+                    "{}={} (attendu {}–{})",
+                    name,
+                    value,
+                    ANALYSIS_WINDOW_RANGE.start(),
+                    ANALYSIS_WINDOW_RANGE.end()
+                ));
+            }
+        }
+        if self.analysis_window_min_seconds > self.analysis_window_max_seconds {
+            errors.push(format!(
+                "analysis_window_min_seconds={} > analysis_window_max_seconds={}",
+                self.analysis_window_min_seconds, self.analysis_window_max_seconds
+            ));
+        }
+        if !ANALYSIS_OFFSET_RANGE.contains(&self.analysis_offset_seconds) {
+            errors.push(format!(
+                "analysis_offset_seconds={} (attendu {}–{})",
+                self.analysis_offset_seconds,
+                ANALYSIS_OFFSET_RANGE.start(),
+                ANALYSIS_OFFSET_RANGE.end()
+            ));
+        }
+        if !CACHE_MAX_ENTRIES_RANGE.contains(&self.cache_max_entries) {
+            errors.push(format!(
+                "cache_max_entries={} (attendu {}–{})",
+                self.cache_max_entries,
+                CACHE_MAX_ENTRIES_RANGE.start(),
+                CACHE_MAX_ENTRIES_RANGE.end()
+            ));
+        }
+        if !ANALYZER_RETRIES_RANGE.contains(&self.analyzer_retries) {
+            errors.push(format!(
+                "analyzer_retries={} (attendu {}–{})",
+                self.analyzer_retries,
+                ANALYZER_RETRIES_RANGE.start(),
+                ANALYZER_RETRIES_RANGE.end()
+            ));
+        }
+        if !MAX_CONCURRENT_ANALYSES_RANGE.contains(&self.max_concurrent_analyses) {
+            errors.push(format!(
+                "max_concurrent_analyses={} (attendu {}–{})",
+                self.max_concurrent_analyses,
+                MAX_CONCURRENT_ANALYSES_RANGE.start(),
+                MAX_CONCURRENT_ANALYSES_RANGE.end()
+            ));
+        }
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            errors.push(format!(
+                "log_level={} (attendu {})",
+                self.log_level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+        if !SCAN_PRIORITIES.contains(&self.scan_priority.as_str()) {
+            errors.push(format!(
+                "scan_priority={} (attendu {})",
+                self.scan_priority,
+                SCAN_PRIORITIES.join(", ")
+            ));
+        }
+        if !UNVERSIONED_CACHE_POLICIES.contains(&self.unversioned_cache.as_str()) {
+            errors.push(format!(
+                "unversioned_cache={} (attendu {})",
+                self.unversioned_cache,
+                UNVERSIONED_CACHE_POLICIES.join(", ")
+            ));
+        }
+        if !ANALYZER_MODES.contains(&self.analyzer_mode.as_str()) {
+            errors.push(format!(
+                "analyzer_mode={} (attendu {})",
+                self.analyzer_mode,
+                ANALYZER_MODES.join(", ")
+            ));
+        }
+        if let Some(limit) = self
+            .download_rate_limit_kbps
+            .filter(|l| !DOWNLOAD_RATE_LIMIT_RANGE.contains(l))
+        {
+            errors.push(format!(
+                "download_rate_limit_kbps={} (attendu {}–{}, 0 = illimité)",
+                limit,
+                DOWNLOAD_RATE_LIMIT_RANGE.start(),
+                DOWNLOAD_RATE_LIMIT_RANGE.end()
+            ));
+        }
+        if self.audio_extensions.iter().all(|e| e.trim().is_empty()) {
+            errors.push("audio_extensions: liste vide".to_string());
+        }
+        if let Some(dir) = self.data_dir.as_deref().filter(|d| !Path::new(d).is_absolute()) {
+            errors.push(format!("data_dir={} (chemin absolu attendu)", dir));
+        }
+        for o in &self.folder_overrides {
+            if o.path.trim().is_empty() {
+                errors.push("folder_overrides: chemin vide".to_string());
+            }
+            if let Some(min) = o.min_bitrate.filter(|m| !MIN_BITRATE_RANGE.contains(m)) {
+                errors.push(format!("folder_overrides[{}].min_bitrate={}", o.path, min));
+            }
+            if let Some(w) = o.analysis_window_seconds.filter(|w| !ANALYSIS_WINDOW_RANGE.contains(w)) {
+                errors.push(format!("folder_overrides[{}].analysis_window_seconds={}", o.path, w));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Clamp every value into its accepted range (used for hand-edited settings files)
+    pub fn clamp(&mut self) {
+        self.min_bitrate = self
+            .min_bitrate
+            .clamp(*MIN_BITRATE_RANGE.start(), *MIN_BITRATE_RANGE.end());
+        self.analysis_window_seconds = self
+            .analysis_window_seconds
+            .clamp(*ANALYSIS_WINDOW_RANGE.start(), *ANALYSIS_WINDOW_RANGE.end());
+        self.analysis_offset_seconds = self
+            .analysis_offset_seconds
+            .clamp(*ANALYSIS_OFFSET_RANGE.start(), *ANALYSIS_OFFSET_RANGE.end());
+        if !ANALYSIS_WINDOW_MODES.contains(&self.analysis_window_mode.as_str()) {
+            self.analysis_window_mode = default_analysis_window_mode();
+        }
+        self.analysis_window_percent = self.analysis_window_percent.clamp(
+            *ANALYSIS_WINDOW_PERCENT_RANGE.start(),
+            *ANALYSIS_WINDOW_PERCENT_RANGE.end(),
+        );
+        self.analysis_window_min_seconds = self
+            .analysis_window_min_seconds
+            .clamp(*ANALYSIS_WINDOW_RANGE.start(), *ANALYSIS_WINDOW_RANGE.end());
+        self.analysis_window_max_seconds = self
+            .analysis_window_max_seconds
+            .clamp(self.analysis_window_min_seconds, *ANALYSIS_WINDOW_RANGE.end());
+        self.cache_max_entries = self
+            .cache_max_entries
+            .clamp(*CACHE_MAX_ENTRIES_RANGE.start(), *CACHE_MAX_ENTRIES_RANGE.end());
+        self.analyzer_retries = self
+            .analyzer_retries
+            .clamp(*ANALYZER_RETRIES_RANGE.start(), *ANALYZER_RETRIES_RANGE.end());
+        self.max_concurrent_analyses = self.max_concurrent_analyses.clamp(
+            *MAX_CONCURRENT_ANALYSES_RANGE.start(),
+            *MAX_CONCURRENT_ANALYSES_RANGE.end(),
+        );
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            self.log_level = default_log_level();
+        }
+        if !SCAN_PRIORITIES.contains(&self.scan_priority.as_str()) {
+            self.scan_priority = default_scan_priority();
+        }
+        if !UNVERSIONED_CACHE_POLICIES.contains(&self.unversioned_cache.as_str()) {
+            self.unversioned_cache = default_unversioned_cache();
+        }
+        if !ANALYZER_MODES.contains(&self.analyzer_mode.as_str()) {
+            self.analyzer_mode = default_analyzer_mode();
+        }
+        if self.data_dir.as_deref().is_some_and(|d| !Path::new(d).is_absolute()) {
+            self.data_dir = None;
+        }
+        self.download_rate_limit_kbps = self
+            .download_rate_limit_kbps
+            .map(|l| l.min(*DOWNLOAD_RATE_LIMIT_RANGE.end()));
+        if self.audio_extensions.iter().all(|e| e.trim().is_empty()) {
+            self.audio_extensions = default_audio_extensions();
+        }
+        self.folder_overrides.retain(|o| !o.path.trim().is_empty());
+        for o in &mut self.folder_overrides {
+            o.min_bitrate = o
+                .min_bitrate
+                .map(|m| m.clamp(*MIN_BITRATE_RANGE.start(), *MIN_BITRATE_RANGE.end()));
+            o.analysis_window_seconds = o
+                .analysis_window_seconds
+                .map(|w| w.clamp(*ANALYSIS_WINDOW_RANGE.start(), *ANALYSIS_WINDOW_RANGE.end()));
+        }
+    }
+
+    /// Extensions a scan picks up: the audio ones, plus video containers when enabled
+    pub fn scan_extensions(&self) -> Vec<String> {
+        let mut extensions = self.audio_extensions.clone();
+        if self.include_video {
+            extensions.extend(self.video_extensions.iter().cloned());
+        }
+        extensions
+    }
+
+    /// Whether `path` is scanned as a video container
+    pub fn is_video(&self, path: &Path) -> bool {
+        self.include_video && crate::audio::is_audio(path, &self.video_extensions)
+    }
+
+    /// `log_level` as a filter, falling back to Info for unknown values
+    pub fn log_level_filter(&self) -> log::LevelFilter {
+        log::LevelFilter::from_str(&self.log_level).unwrap_or(log::LevelFilter::Info)
+    }
+
+    /// Most specific override whose folder contains `path`, if any
+    pub fn override_for(&self, path: &Path) -> Option<&FolderOverride> {
+        self.folder_overrides
+            .iter()
+            .filter(|o| path.starts_with(&o.path))
+            .max_by_key(|o| Path::new(&o.path).components().count())
+    }
+
+    /// Effective (min_bitrate, analysis_window_seconds) for a file. A value the caller
+    /// passed explicitly wins; otherwise the folder override, then the global setting.
+    pub fn thresholds_for(&self, path: &Path, min: Option<u32>, window: Option<u32>) -> (u32, u32) {
+        let folder = self.override_for(path);
+        (
+            min.or_else(|| folder.and_then(|o| o.min_bitrate))
+                .unwrap_or(self.min_bitrate),
+            window
+                .or_else(|| folder.and_then(|o| o.analysis_window_seconds))
+                .unwrap_or(self.analysis_window_seconds),
+        )
+    }
+
+    /// Download speed cap in kbps, None when unlimited
+    pub fn download_rate_limit(&self) -> Option<u32> {
+        self.download_rate_limit_kbps.filter(|&l| l > 0)
+    }
+
+    /// Seconds to analyze of a file lasting `duration`: `window` in "fixed" mode, else
+    /// analysis_window_percent of the duration within the min/max bounds. `window` is
+    /// also the answer when the duration is unknown.
+    pub fn window_for_duration(&self, window: u32, duration: Option<f64>) -> u32 {
+        match duration.filter(|d| *d > 0.0) {
+            Some(d) if self.analysis_window_mode == "fraction" => {
+                let seconds = (d * self.analysis_window_percent as f64 / 100.0).round() as u32;
+                seconds.clamp(self.analysis_window_min_seconds, self.analysis_window_max_seconds)
+            }
+            _ => window,
+        }
+    }
+}
+
+/// Folder under the system temp dir used when no other data location is available
+const TEMP_DATA_DIR: &str = "keson-spectral-improver";
+
+/// File next to the executable that switches the app to portable mode
+pub const PORTABLE_MARKER: &str = "portable.txt";
+/// Environment variable that switches the app to portable mode; "0" or empty keeps it off
+pub const PORTABLE_ENV: &str = "KESON_PORTABLE";
+
+/// Portable data folder for an executable living in `exe_dir`: `<exe_dir>/data` when
+/// the marker file sits next to the executable or `env` holds a value other than "0"
+fn portable_dir_in(exe_dir: &Path, env: Option<&str>) -> Option<PathBuf> {
+    let by_env = env.is_some_and(|v| !v.trim().is_empty() && v.trim() != "0");
+    if by_env || exe_dir.join(PORTABLE_MARKER).is_file() {
+        Some(exe_dir.join("data"))
+    } else {
+        None
+    }
+}
+
+/// Data folder of a portable install, if the app runs as one. Settings, cache, results
+/// and logs all live under it; detection happens once per run.
+pub fn portable_data_dir() -> Option<PathBuf> {
+    static PORTABLE: std::sync::OnceLock<Option<PathBuf>> = std::sync::OnceLock::new();
+    PORTABLE
+        .get_or_init(|| {
+            let exe = std::env::current_exe().ok()?;
+            portable_dir_in(exe.parent()?, std::env::var(PORTABLE_ENV).ok().as_deref())
+        })
+        .clone()
+}
+
+/// Where the logs are written: `<portable data>/logs` or the platform log dir
+pub fn log_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match portable_data_dir() {
+        Some(dir) => Ok(dir.join("logs")),
+        None => app.path().app_log_dir().map_err(|e| e.to_string()),
+    }
+}
+
+/// Where the cache, results and other app data live, and which fallback provided it
+#[derive(Serialize, Clone, Debug)]
+pub struct DataDir {
+    pub path: String,
+    /// "portable", "app_data_dir", "app_cache_dir", "setting" or "temp"
+    pub source: &'static str,
+}
+
+fn platform_data_dir(app: &tauri::AppHandle) -> Option<(PathBuf, &'static str)> {
+    if let Some(dir) = portable_data_dir() {
+        return Some((dir, "portable"));
+    }
     app.path()
         .app_data_dir()
-        .or_else(|_| app.path().app_cache_dir())
-        .unwrap_or_else(|_| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+        .map(|p| (p, "app_data_dir"))
+        .or_else(|_| app.path().app_cache_dir().map(|p| (p, "app_cache_dir")))
+        .ok()
+}
+
+fn temp_data_dir() -> PathBuf {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    let dir = std::env::temp_dir().join(TEMP_DATA_DIR);
+    WARNED.call_once(|| {
+        log::warn!(
+            "[settings] No data dir available, using {:?}; it may not survive a reboot",
+            dir
+        );
+    });
+    dir
+}
+
+/// Resolve the data folder: the portable folder, app_data_dir, then app_cache_dir, then
+/// the `data_dir` setting, and a temp folder as the last resort
+pub fn resolve_data_dir(app: &tauri::AppHandle) -> DataDir {
+    let (path, source) = platform_data_dir(app)
+        .or_else(|| {
+            load_settings(app)
+                .data_dir
+                .filter(|d| !d.trim().is_empty())
+                .map(|d| (PathBuf::from(d), "setting"))
+        })
+        .unwrap_or_else(|| (temp_data_dir(), "temp"));
+    DataDir {
+        path: path.to_string_lossy().to_string(),
+        source,
+    }
+}
+
+pub fn data_dir(app: &tauri::AppHandle) -> PathBuf {
+    PathBuf::from(resolve_data_dir(app).path)
+}
+
+/// settings.json can't live in the `data_dir` it configures, so it skips that step
+pub fn settings_path(app: &tauri::AppHandle) -> PathBuf {
+    platform_data_dir(app)
+        .map(|(dir, _)| dir)
+        .unwrap_or_else(temp_data_dir)
         .join("settings.json")
 }
 
 pub fn load_settings(app: &tauri::AppHandle) -> Settings {
     let path = settings_path(app);
-    if let Ok(text) = fs::read_to_string(&path) {
-        serde_json::from_str(&text).unwrap_or_default()
-    } else {
-        Settings::default()
+    let mut settings = match fs::read_to_string(&path) {
+        Ok(text) => parse_settings(&path, &text),
+        Err(_) => Settings::default(),
+    };
+    if let Err(errors) = settings.validate() {
+        log::warn!("[settings] Clamping out-of-range values: {}", errors.join(", "));
+        settings.clamp();
+    }
+    settings
+}
+
+/// Parse settings.json, migrating older or partially-invalid shapes instead of
+/// silently resetting everything. Defaults are the last resort, after a .bak copy.
+fn parse_settings(path: &Path, text: &str) -> Settings {
+    match serde_json::from_str::<Settings>(text) {
+        Ok(settings) if settings.version >= SETTINGS_VERSION => return settings,
+        Ok(settings) => {
+            let from = settings.version;
+            let upgraded = upgrade(settings);
+            log::info!("[settings] Upgraded settings from v{} to v{}", from, SETTINGS_VERSION);
+            persist_migrated(path, &upgraded);
+            return upgraded;
+        }
+        Err(e) => log::warn!("[settings] settings.json does not match the current schema: {}", e),
+    }
+
+    backup_settings_file(path, text);
+
+    match serde_json::from_str::<serde_json::Value>(text).ok().and_then(migrate_value) {
+        Some(settings) => {
+            log::info!("[settings] Migrated settings.json to v{}", SETTINGS_VERSION);
+            persist_migrated(path, &settings);
+            settings
+        }
+        None => {
+            log::warn!("[settings] Unreadable settings.json, falling back to defaults");
+            Settings::default()
+        }
+    }
+}
+
+/// Salvage every field that still deserializes from an older or damaged settings object,
+/// filling the rest from defaults
+fn migrate_value(value: serde_json::Value) -> Option<Settings> {
+    let old = value.as_object()?;
+    let mut merged = serde_json::to_value(Settings::default()).ok()?;
+
+    for (key, old_value) in old {
+        let Some(fields) = merged.as_object_mut() else { break };
+        if !fields.contains_key(key) {
+            continue;
+        }
+        let previous = fields.insert(key.clone(), old_value.clone());
+        if serde_json::from_value::<Settings>(merged.clone()).is_err() {
+            log::warn!("[settings] Dropping incompatible value for '{}'", key);
+            if let (Some(fields), Some(prev)) = (merged.as_object_mut(), previous) {
+                fields.insert(key.clone(), prev);
+            }
+        }
+    }
+
+    let settings: Settings = serde_json::from_value(merged).ok()?;
+    Some(upgrade(settings))
+}
+
+/// Apply per-version upgrade steps up to SETTINGS_VERSION
+fn upgrade(mut settings: Settings) -> Settings {
+    // v0 -> v1: versioning introduced, no field changes
+    if settings.version < 1 {
+        settings.version = 1;
+    }
+    // v1 -> v2: AIFF, WavPack, Musepack and Monkey's Audio become default formats
+    if settings.version < 2 {
+        for ext in V2_AUDIO_EXTENSIONS {
+            if !settings.audio_extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)) {
+                settings.audio_extensions.push(ext.to_string());
+            }
+        }
+        settings.version = 2;
+    }
+    settings
+}
+
+fn backup_settings_file(path: &Path, text: &str) {
+    let bak = path.with_extension("json.bak");
+    match fs::write(&bak, text) {
+        Ok(_) => log::warn!("[settings] Saved a copy of the previous settings to {:?}", bak),
+        Err(e) => log::error!("[settings] Failed to back up settings to {:?}: {}", bak, e),
+    }
+}
+
+fn persist_migrated(path: &Path, settings: &Settings) {
+    if let Err(e) = fs::write(path, serde_json::to_string_pretty(settings).unwrap_or_default()) {
+        log::error!("[settings] Failed to write migrated settings: {}", e);
     }
 }
 
+/// Returned by save_settings so the UI can tell the user when a restart is needed
+#[derive(Serialize)]
+pub struct SettingsSaved {
+    /// The global rayon pool is built once at startup, so a new thread count
+    /// only takes effect after restarting the app
+    pub restart_required: bool,
+}
+
+/// Which folder holds the app's data and why, for "why isn't my cache persisting?"
+#[tauri::command]
+pub fn get_data_dir(app: tauri::AppHandle) -> DataDir {
+    resolve_data_dir(&app)
+}
+
 #[tauri::command]
 pub fn get_settings(app: tauri::AppHandle) -> Settings {
     load_settings(&app)
 }
 
 #[tauri::command]
-pub fn save_settings(app: tauri::AppHandle, settings: Settings) -> Result<(), String> {
+pub fn save_settings(app: tauri::AppHandle, settings: Settings) -> Result<SettingsSaved, String> {
+    settings
+        .validate()
+        .map_err(|errors| format!("Paramètres invalides: {}", errors.join(", ")))?;
+
+    // Unix scan threads can't raise their priority back once lowered
+    let leaves_low_priority =
+        load_settings(&app).scan_priority == "low" && settings.scan_priority != "low";
+
     let path = settings_path(&app);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
@@ -61,5 +721,206 @@ pub fn save_settings(app: tauri::AppHandle, settings: Settings) -> Result<(), St
         serde_json::to_string_pretty(&settings).unwrap_or_default(),
     )
     .map_err(|e| e.to_string())?;
-    Ok(())
+    log::set_max_level(settings.log_level_filter());
+    Ok(SettingsSaved {
+        restart_required: crate::resolve_thread_count(settings.rayon_threads)
+            != rayon::current_num_threads()
+            || (cfg!(unix) && leaves_low_priority),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_value_fills_missing_fields() {
+        let old = serde_json::json!({
+            "min_bitrate": 320,
+            "analysis_window_seconds": 60
+        });
+        let settings = migrate_value(old).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.min_bitrate, 320);
+        assert_eq!(settings.analysis_window_seconds, 60);
+        assert_eq!(settings.cache_max_entries, Settings::default().cache_max_entries);
+        assert!(settings.skip_hidden);
+        assert!(!settings.skip_replaced);
+    }
+
+    #[test]
+    fn test_migrate_value_drops_incompatible_fields() {
+        let old = serde_json::json!({
+            "min_bitrate": "high",
+            "cache_enabled": false,
+            "client_token": "abc"
+        });
+        let settings = migrate_value(old).unwrap();
+        assert_eq!(settings.min_bitrate, Settings::default().min_bitrate);
+        assert!(!settings.cache_enabled);
+        assert_eq!(settings.client_token.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_migrate_value_rejects_non_objects() {
+        assert!(migrate_value(serde_json::json!([1, 2, 3])).is_none());
+    }
+
+    #[test]
+    fn test_thresholds_for_picks_most_specific_override() {
+        let settings = Settings {
+            folder_overrides: vec![
+                FolderOverride {
+                    path: "/music".into(),
+                    min_bitrate: Some(128),
+                    analysis_window_seconds: None,
+                },
+                FolderOverride {
+                    path: "/music/archive".into(),
+                    min_bitrate: Some(900),
+                    analysis_window_seconds: Some(30),
+                },
+            ],
+            min_bitrate: 256,
+            analysis_window_seconds: 100,
+            ..Settings::default()
+        };
+
+        assert_eq!(settings.thresholds_for(Path::new("/music/archive/a.flac"), None, None), (900, 30));
+        assert_eq!(settings.thresholds_for(Path::new("/music/car/b.mp3"), None, None), (128, 100));
+        assert_eq!(settings.thresholds_for(Path::new("/musicals/c.mp3"), None, None), (256, 100));
+        // Thresholds the caller passed explicitly win over the folder's
+        assert_eq!(
+            settings.thresholds_for(Path::new("/music/archive/a.flac"), Some(192), None),
+            (192, 30)
+        );
+        assert_eq!(
+            settings.thresholds_for(Path::new("/music/archive/a.flac"), None, Some(60)),
+            (900, 60)
+        );
+    }
+
+    #[test]
+    fn test_scan_extensions_add_video_only_when_enabled() {
+        let mut settings = Settings::default();
+        assert!(!settings.scan_extensions().contains(&"mkv".to_string()));
+        assert!(!settings.is_video(Path::new("/clips/a.mkv")));
+        settings.include_video = true;
+        assert!(settings.scan_extensions().contains(&"mkv".to_string()));
+        assert!(settings.is_video(Path::new("/clips/a.MKV")));
+        assert!(!settings.is_video(Path::new("/music/a.mp3")));
+    }
+
+    #[test]
+    fn test_default_settings_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_accepts_boundaries() {
+        let mut settings = Settings {
+            min_bitrate: 32,
+            analysis_window_seconds: 600,
+            cache_max_entries: 0,
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        settings.min_bitrate = 2000;
+        settings.analysis_window_seconds = 5;
+        settings.cache_max_entries = 1_000_000;
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_lists_every_rejected_value() {
+        let settings = Settings {
+            min_bitrate: 31,
+            analysis_window_seconds: 601,
+            cache_max_entries: 1_000_001,
+            ..Settings::default()
+        };
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("min_bitrate=31"));
+        assert!(errors[1].starts_with("analysis_window_seconds=601"));
+        assert!(errors[2].starts_with("cache_max_entries=1000001"));
+    }
+
+    #[test]
+    fn test_clamp_pulls_values_into_range() {
+        let mut settings = Settings {
+            min_bitrate: 0,
+            analysis_window_seconds: 100_000,
+            cache_max_entries: usize::MAX,
+            ..Settings::default()
+        };
+        settings.clamp();
+        assert_eq!(settings.min_bitrate, 32);
+        assert_eq!(settings.analysis_window_seconds, 600);
+        assert_eq!(settings.cache_max_entries, 1_000_000);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_log_level_is_rejected_and_reset() {
+        let mut settings = Settings {
+            log_level: "verbose".into(),
+            ..Settings::default()
+        };
+        assert_eq!(settings.log_level_filter(), log::LevelFilter::Info);
+        assert!(settings.validate().unwrap_err()[0].starts_with("log_level=verbose"));
+        settings.clamp();
+        assert_eq!(settings.log_level, "info");
+    }
+
+    #[test]
+    fn test_relative_data_dir_is_rejected_and_dropped() {
+        let mut settings = Settings {
+            data_dir: Some("keson-data".into()),
+            ..Settings::default()
+        };
+        assert!(settings.validate().unwrap_err()[0].starts_with("data_dir=keson-data"));
+        settings.clamp();
+        assert_eq!(settings.data_dir, None);
+    }
+
+    #[test]
+    fn test_portable_dir_follows_marker_or_env() {
+        let exe_dir = std::env::temp_dir().join(format!("ksi_portable_test_{}", std::process::id()));
+        fs::create_dir_all(&exe_dir).unwrap();
+        let data = exe_dir.join("data");
+        assert_eq!(portable_dir_in(&exe_dir, None), None);
+        assert_eq!(portable_dir_in(&exe_dir, Some("0")), None);
+        assert_eq!(portable_dir_in(&exe_dir, Some("1")), Some(data.clone()));
+
+        fs::write(exe_dir.join(PORTABLE_MARKER), "").unwrap();
+        assert_eq!(portable_dir_in(&exe_dir, None), Some(data.clone()));
+        assert_eq!(portable_dir_in(&exe_dir, Some("0")), Some(data));
+        let _ = fs::remove_dir_all(&exe_dir);
+    }
+
+    #[test]
+    fn test_fraction_window_follows_duration_within_bounds() {
+        let fixed = Settings::default();
+        assert_eq!(fixed.window_for_duration(100, Some(600.0)), 100);
+
+        let settings = Settings {
+            analysis_window_mode: "fraction".into(),
+            ..Default::default()
+        };
+        assert_eq!(settings.window_for_duration(100, Some(200.0)), 60);
+        assert_eq!(settings.window_for_duration(100, Some(30.0)), 20);
+        assert_eq!(settings.window_for_duration(100, Some(3600.0)), 120);
+        assert_eq!(settings.window_for_duration(100, None), 100);
+
+        let mut inverted = Settings {
+            analysis_window_min_seconds: 200,
+            analysis_window_max_seconds: 60,
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+        inverted.clamp();
+        assert_eq!(inverted.analysis_window_max_seconds, 200);
+    }
 }
@@ -1,15 +1,92 @@
 use lofty::config::WriteOptions;
+use lofty::picture::{MimeType, PictureType};
 use lofty::prelude::*;
 use lofty::probe::Probe;
+use lofty::file::TaggedFile;
 use lofty::tag::Tag;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::types::{CacheEntry, ExtractedMetadata};
 
 /// Tag key used to mark files as replaced by Keson
 const KESON_TAG_KEY: &str = "KESON_REPLACED";
 
+/// Comment key of an analysis embedded in the file (see write_analysis_tag)
+const ANALYSIS_TAG_KEY: &str = "KESON_ANALYSIS";
+
+/// Comment key recording where a download came from (see write_download_tags)
+const SOURCE_TAG_KEY: &str = "KESON_SOURCE";
+
+/// How long a file tagged by the app is taken for its own write by folder watchers;
+/// comfortably longer than their debounce and settle delays
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Files the app tagged recently, with when
+static OWN_WRITES: Mutex<Vec<(PathBuf, Instant)>> = Mutex::new(Vec::new());
+
+fn note_own_write(path: &Path) {
+    let mut writes = OWN_WRITES.lock().unwrap_or_else(|e| e.into_inner());
+    writes.retain(|(p, at)| p != path && at.elapsed() < OWN_WRITE_WINDOW);
+    writes.push((path.to_path_buf(), Instant::now()));
+}
+
+/// Whether the app wrote tags to `path` in the last few seconds, so the folder watcher
+/// doesn't analyze a file again because we tagged it
+pub fn written_recently(path: &Path) -> bool {
+    OWN_WRITES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|(p, at)| p == path && at.elapsed() < OWN_WRITE_WINDOW)
+}
+
+/// Save `tag` to `path`, recording the write for written_recently
+fn save_tag(tag: &Tag, path: &Path) -> Result<(), String> {
+    note_own_write(path);
+    let saved = tag
+        .save_to_path(path, WriteOptions::default())
+        .map_err(|e| format!("Failed to save tag: {}", e));
+    // Again once done, so a slow write still gets the full window
+    note_own_write(path);
+    saved
+}
+
+/// Extensions lofty can read and write tags for. Others (webm, dsf...) are
+/// analyzed but never tagged.
+const TAGGABLE_EXTENSIONS: [&str; 13] = [
+    "mp3", "m4a", "aac", "wav", "flac", "ogg", "opus", "aiff", "aif", "wv", "mpc", "ape", "m4b",
+];
+
+/// Whether write_replaced_tag can tag this file, judged by its extension
+pub fn supports_tagging(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| TAGGABLE_EXTENSIONS.iter().any(|t| t.eq_ignore_ascii_case(ext)))
+}
+
+/// Get the primary tag, falling back to the first one, and create a primary tag
+/// when the file has none. None if the format can't hold tags.
+fn tag_for_writing(tagged_file: &mut TaggedFile) -> Option<&mut Tag> {
+    if tagged_file.primary_tag().is_none() && tagged_file.first_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    if tagged_file.primary_tag().is_some() {
+        tagged_file.primary_tag_mut()
+    } else {
+        tagged_file.first_tag_mut()
+    }
+}
+
 /// Write the KESON_REPLACED tag to an audio file.
 /// Returns Ok(true) if successful, Ok(false) if file format not supported.
 pub fn write_replaced_tag(path: &Path) -> Result<bool, String> {
+    if !supports_tagging(path) {
+        return Ok(false);
+    }
+
     let mut tagged_file = match Probe::open(path) {
         Ok(probe) => match probe.read() {
             Ok(file) => file,
@@ -18,22 +95,8 @@ pub fn write_replaced_tag(path: &Path) -> Result<bool, String> {
         Err(e) => return Err(format!("Failed to open file: {}", e)),
     };
 
-    // Get or create the primary tag
-    let tag = match tagged_file.primary_tag_mut() {
-        Some(t) => t,
-        None => {
-            if let Some(first_tag) = tagged_file.first_tag_mut() {
-                first_tag
-            } else {
-                // Create a new tag
-                let tag_type = tagged_file.primary_tag_type();
-                tagged_file.insert_tag(Tag::new(tag_type));
-                match tagged_file.primary_tag_mut() {
-                    Some(t) => t,
-                    None => return Ok(false), // Format doesn't support tags
-                }
-            }
-        }
+    let Some(tag) = tag_for_writing(&mut tagged_file) else {
+        return Ok(false); // Format doesn't support tags
     };
 
     // Get current timestamp
@@ -56,43 +119,324 @@ pub fn write_replaced_tag(path: &Path) -> Result<bool, String> {
     tag.set_comment(new_comment);
 
     // Save back to file
-    tag.save_to_path(path, WriteOptions::default())
-        .map_err(|e| format!("Failed to save tag: {}", e))?;
+    save_tag(tag, path)?;
 
-    println!("[tagging] Wrote KESON_REPLACED tag to: {:?}", path);
+    log::info!("[tagging] Wrote KESON_REPLACED tag to: {:?}", path);
     Ok(true)
 }
 
-/// Check if an audio file has the KESON_REPLACED tag.
-/// Returns Ok(true) if tagged, Ok(false) if not tagged or not supported.
-pub fn has_replaced_tag(path: &Path) -> bool {
-    let tagged_file = match Probe::open(path) {
+/// Write artist, title and album back to a file. Fields left as None (or empty) keep
+/// their current value, as do all other tags; duration and ISRC are never written.
+/// Returns Ok(false) if the format can't be tagged.
+pub fn write_tags(path: &Path, metadata: &ExtractedMetadata) -> Result<bool, String> {
+    if !supports_tagging(path) {
+        return Ok(false);
+    }
+
+    let mut tagged_file = match Probe::open(path) {
         Ok(probe) => match probe.read() {
             Ok(file) => file,
-            Err(_) => return false,
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
         },
-        Err(_) => return false,
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
     };
 
-    // Check primary tag first, then any tag
-    if let Some(tag) = tagged_file.primary_tag() {
-        if let Some(comment) = tag.comment() {
-            if comment.contains(KESON_TAG_KEY) {
-                return true;
-            }
-        }
+    let Some(tag) = tag_for_writing(&mut tagged_file) else {
+        return Ok(false);
+    };
+    set_metadata(tag, metadata);
+    save_tag(tag, path)?;
+
+    log::info!("[tagging] Wrote metadata to: {:?}", path);
+    Ok(true)
+}
+
+/// Set artist, title and album on `tag`, leaving None or empty fields as they are
+fn set_metadata(tag: &mut Tag, metadata: &ExtractedMetadata) {
+    let value = |v: &Option<String>| {
+        v.as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    if let Some(artist) = value(&metadata.artist) {
+        tag.set_artist(artist);
+    }
+    if let Some(title) = value(&metadata.title) {
+        tag.set_title(title);
+    }
+    if let Some(album) = value(&metadata.album) {
+        tag.set_album(album);
     }
+}
+
+/// ReplayGain values to write: gains in dB, peaks as linear amplitude (1.0 = full scale)
+pub struct ReplayGainTags {
+    pub track_gain: f64,
+    pub track_peak: f64,
+    /// (gain, peak) for the album, written only when present
+    pub album: Option<(f64, f64)>,
+}
+
+/// Write REPLAYGAIN_* tags, replacing existing ones; other tags are kept.
+/// Returns Ok(false) if the format can't be tagged.
+pub fn write_replaygain_tags(path: &Path, gains: &ReplayGainTags) -> Result<bool, String> {
+    if !supports_tagging(path) {
+        return Ok(false);
+    }
+
+    let mut tagged_file = match Probe::open(path) {
+        Ok(probe) => match probe.read() {
+            Ok(file) => file,
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
+        },
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
+    };
+
+    let Some(tag) = tag_for_writing(&mut tagged_file) else {
+        return Ok(false);
+    };
 
-    // Check all tags
-    for tag in tagged_file.tags() {
-        if let Some(comment) = tag.comment() {
-            if comment.contains(KESON_TAG_KEY) {
-                return true;
-            }
+    tag.insert_text(ItemKey::ReplayGainTrackGain, format!("{:.2} dB", gains.track_gain));
+    tag.insert_text(ItemKey::ReplayGainTrackPeak, format!("{:.6}", gains.track_peak));
+    if let Some((gain, peak)) = gains.album {
+        tag.insert_text(ItemKey::ReplayGainAlbumGain, format!("{:.2} dB", gain));
+        tag.insert_text(ItemKey::ReplayGainAlbumPeak, format!("{:.6}", peak));
+    }
+
+    save_tag(tag, path)?;
+
+    log::info!("[tagging] Wrote ReplayGain tags to: {:?}", path);
+    Ok(true)
+}
+
+/// Comment line holding an embedded analysis
+fn format_analysis(entry: &CacheEntry) -> String {
+    let mut fields = Vec::new();
+    if let Some(b) = entry.bitrate {
+        fields.push(format!("bitrate={}", b));
+    }
+    if let Some(c) = entry.cutoff_hz {
+        fields.push(format!("cutoff={}", c));
+    }
+    if let Some(l) = entry.is_lossless {
+        fields.push(format!("lossless={}", u8::from(l)));
+    }
+    if let Some(v) = &entry.analyzer_version {
+        fields.push(format!("version={}", v));
+    }
+    if let Some(o) = entry.analysis_offset {
+        fields.push(format!("offset={}", o));
+    }
+    if let Some(w) = entry.analysis_window {
+        fields.push(format!("window={}", w));
+    }
+    format!("{}={}", ANALYSIS_TAG_KEY, fields.join(";"))
+}
+
+/// Parse the KESON_ANALYSIS line out of a comment; unknown fields are ignored
+fn parse_analysis(comment: &str) -> Option<CacheEntry> {
+    let prefix = format!("{}=", ANALYSIS_TAG_KEY);
+    let line = comment.lines().find_map(|l| l.trim().strip_prefix(prefix.as_str()))?;
+    let mut entry = CacheEntry::default();
+    for field in line.split(';') {
+        match field.split_once('=') {
+            Some(("bitrate", v)) => entry.bitrate = v.parse().ok(),
+            Some(("cutoff", v)) => entry.cutoff_hz = v.parse().ok(),
+            Some(("lossless", v)) => entry.is_lossless = Some(v == "1"),
+            Some(("version", v)) if !v.is_empty() => entry.analyzer_version = Some(v.to_string()),
+            Some(("offset", v)) => entry.analysis_offset = v.parse().ok(),
+            Some(("window", v)) => entry.analysis_window = v.parse().ok(),
+            _ => {}
         }
     }
+    Some(entry)
+}
+
+/// Store an analysis in the file's comment as a KESON_ANALYSIS line, replacing an
+/// earlier one and keeping the rest of the comment (including KESON_REPLACED).
+/// Returns Ok(false) if the format can't be tagged.
+pub fn write_analysis_tag(path: &Path, entry: &CacheEntry) -> Result<bool, String> {
+    if !supports_tagging(path) {
+        return Ok(false);
+    }
+
+    let mut tagged_file = match Probe::open(path) {
+        Ok(probe) => match probe.read() {
+            Ok(file) => file,
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
+        },
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
+    };
+
+    let Some(tag) = tag_for_writing(&mut tagged_file) else {
+        return Ok(false);
+    };
+
+    let line = format_analysis(entry);
+    let existing_comment = tag.comment().unwrap_or_default().to_string();
+    tag.set_comment(with_comment_line(&existing_comment, ANALYSIS_TAG_KEY, line));
+
+    save_tag(tag, path)?;
+
+    log::info!("[tagging] Wrote KESON_ANALYSIS tag to: {:?}", path);
+    Ok(true)
+}
+
+/// `comment` with its `key=` line replaced by `line`, or `line` appended when it has none
+fn with_comment_line(comment: &str, key: &str, line: String) -> String {
+    if comment.contains(key) {
+        let re = regex::Regex::new(&format!(r"{}=[^\n]*", regex::escape(key))).unwrap();
+        re.replace(comment, regex::NoExpand(&line)).to_string()
+    } else if comment.is_empty() {
+        line
+    } else {
+        format!("{}\n{}", comment, line)
+    }
+}
+
+/// Tag a fresh download with the artist, title and album resolved for it (None fields
+/// keep what the file has) and a KESON_SOURCE line naming the source and link.
+/// Returns Ok(false) if the format can't be tagged. The file is saved once.
+pub fn write_download_tags(
+    path: &Path,
+    metadata: &ExtractedMetadata,
+    source: &str,
+    url: &str,
+) -> Result<bool, String> {
+    if !supports_tagging(path) {
+        return Ok(false);
+    }
+
+    let mut tagged_file = Probe::open(path)
+        .and_then(|probe| probe.read())
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let Some(tag) = tag_for_writing(&mut tagged_file) else {
+        return Ok(false);
+    };
+    set_metadata(tag, metadata);
+    let line = format!("{}=from={};url={}", SOURCE_TAG_KEY, source, url);
+    let existing_comment = tag.comment().unwrap_or_default().to_string();
+    tag.set_comment(with_comment_line(&existing_comment, SOURCE_TAG_KEY, line));
+    save_tag(tag, path)?;
+
+    log::info!("[tagging] Tagged download {:?} from {}", path, source);
+    Ok(true)
+}
+
+/// The analysis embedded by write_analysis_tag, if the file has one
+pub fn read_analysis_tag(path: &Path) -> Option<CacheEntry> {
+    let tagged_file = Probe::open(path).and_then(|probe| probe.read()).ok()?;
+    let comment = tagged_file
+        .primary_tag()
+        .and_then(|t| t.comment())
+        .filter(|c| c.contains(ANALYSIS_TAG_KEY))
+        .or_else(|| {
+            tagged_file
+                .tags()
+                .iter()
+                .filter_map(|t| t.comment())
+                .find(|c| c.contains(ANALYSIS_TAG_KEY))
+        })?;
+    parse_analysis(&comment)
+}
+
+/// Whether any tag of the file carries KESON_REPLACED
+fn is_replaced(tagged_file: &TaggedFile) -> bool {
+    tagged_file
+        .tags()
+        .iter()
+        .filter_map(|t| t.comment())
+        .any(|c| c.contains(KESON_TAG_KEY))
+}
+
+/// Check if an audio file has the KESON_REPLACED tag.
+/// False when it isn't tagged, can't be read or isn't supported.
+pub fn has_replaced_tag(path: &Path) -> bool {
+    Probe::open(path)
+        .and_then(|probe| probe.read())
+        .is_ok_and(|tagged_file| is_replaced(&tagged_file))
+}
+
+/// Parse a ReplayGain value such as "-6.54 dB" (the unit is optional)
+fn parse_gain(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("DB"))
+        .or_else(|| value.strip_suffix("db"))
+        .unwrap_or(value);
+    number.trim().parse::<f64>().ok().filter(|g| g.is_finite())
+}
+
+/// Tags a scan row shows, read together so the file is parsed once
+#[derive(Debug, Default, PartialEq)]
+pub struct ScanTags {
+    /// Carries the KESON_REPLACED tag
+    pub replaced: bool,
+    /// ReplayGain gains in dB, from the first tag carrying each
+    pub replaygain_track_gain: Option<f64>,
+    pub replaygain_album_gain: Option<f64>,
+}
+
+/// Replaced flag and ReplayGain gains of a file; all unset when it can't be read
+pub fn read_scan_tags(path: &Path) -> ScanTags {
+    let Ok(tagged_file) = Probe::open(path).and_then(|probe| probe.read()) else {
+        return ScanTags::default();
+    };
+    let gain = |key: ItemKey| {
+        tagged_file
+            .tags()
+            .iter()
+            .find_map(|tag| tag.get_string(&key).and_then(parse_gain))
+    };
+    ScanTags {
+        replaced: is_replaced(&tagged_file),
+        replaygain_track_gain: gain(ItemKey::ReplayGainTrackGain),
+        replaygain_album_gain: gain(ItemKey::ReplayGainAlbumGain),
+    }
+}
+
+/// MIME type of an embedded picture, sniffed from its bytes first since taggers
+/// often store a PNG labelled image/jpeg (or no type at all)
+pub fn picture_mime(data: &[u8], declared: Option<&MimeType>) -> String {
+    let sniffed = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if data.starts_with(b"BM") {
+        Some("image/bmp")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    };
+    match (sniffed, declared) {
+        (Some(mime), _) => mime.to_string(),
+        (None, Some(m)) if m.as_str().starts_with("image/") => m.as_str().to_string(),
+        _ => "image/jpeg".to_string(),
+    }
+}
+
+/// Embedded cover of an audio file: the front cover if one is marked as such,
+/// otherwise the first picture. Returns the image bytes and their MIME type.
+pub fn read_front_cover(path: &Path) -> Result<Option<(Vec<u8>, String)>, String> {
+    let tagged_file = match Probe::open(path) {
+        Ok(probe) => match probe.read() {
+            Ok(file) => file,
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
+        },
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
+    };
+
+    let pictures = || tagged_file.tags().iter().flat_map(|t| t.pictures());
+    let picture = pictures()
+        .find(|p| p.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures().next());
 
-    false
+    Ok(picture.map(|p| (p.data().to_vec(), picture_mime(p.data(), p.mime_type()))))
 }
 
 #[cfg(test)]
@@ -104,5 +448,133 @@ mod tests {
     fn test_has_replaced_tag_nonexistent() {
         let path = PathBuf::from("/nonexistent/file.mp3");
         assert!(!has_replaced_tag(&path));
+        assert_eq!(read_scan_tags(&path), ScanTags::default());
+    }
+
+    #[test]
+    fn test_supports_tagging_by_extension() {
+        assert!(supports_tagging(Path::new("/music/a.AIFF")));
+        assert!(supports_tagging(Path::new("/music/b.wv")));
+        assert!(supports_tagging(Path::new("/music/c.mpc")));
+        assert!(supports_tagging(Path::new("/music/d.ape")));
+        assert!(!supports_tagging(Path::new("/music/e.webm")));
+        assert!(!supports_tagging(Path::new("/music/noext")));
+    }
+
+    #[test]
+    fn test_write_replaced_tag_skips_untaggable_formats() {
+        let path = std::env::temp_dir().join("ksi_tagging_test.webm");
+        std::fs::write(&path, b"not really webm").unwrap();
+        assert_eq!(write_replaced_tag(&path), Ok(false));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Minimal valid mono 16-bit PCM WAV with a few silent samples
+    fn write_sample_wav(path: &Path) {
+        let samples = [0u8; 800];
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&samples);
+        std::fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn test_write_tags_updates_given_fields_and_keeps_others() {
+        let path = std::env::temp_dir().join(format!("ksi_write_tags_{}.wav", std::process::id()));
+        write_sample_wav(&path);
+
+        let first = ExtractedMetadata {
+            artist: Some("Old Artist".into()),
+            title: Some("Song".into()),
+            album: Some("Album".into()),
+            ..Default::default()
+        };
+        assert!(!written_recently(&path));
+        assert_eq!(write_tags(&path, &first), Ok(true));
+        // The folder watcher ignores the events of our own write
+        assert!(written_recently(&path));
+        let fix = ExtractedMetadata {
+            artist: Some("New Artist".into()),
+            ..Default::default()
+        };
+        assert_eq!(write_tags(&path, &fix), Ok(true));
+
+        let tagged = Probe::open(&path).unwrap().read().unwrap();
+        let tag = tagged.primary_tag().or(tagged.first_tag()).unwrap();
+        assert_eq!(tag.artist().as_deref(), Some("New Artist"));
+        assert_eq!(tag.title().as_deref(), Some("Song"));
+        assert_eq!(tag.album().as_deref(), Some("Album"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_picture_mime_prefers_sniffed_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n....";
+        assert_eq!(picture_mime(png, Some(&MimeType::Jpeg)), "image/png");
+        assert_eq!(picture_mime(&[0xFF, 0xD8, 0xFF, 0xE0], None), "image/jpeg");
+        assert_eq!(picture_mime(b"????", Some(&MimeType::Gif)), "image/gif");
+        assert_eq!(picture_mime(b"????", None), "image/jpeg");
+    }
+
+    #[test]
+    fn test_parse_gain_accepts_values_with_or_without_unit() {
+        assert_eq!(parse_gain("-6.54 dB"), Some(-6.54));
+        assert_eq!(parse_gain("+2.10 db"), Some(2.1));
+        assert_eq!(parse_gain(" -0.5"), Some(-0.5));
+        assert_eq!(parse_gain("loud"), None);
+        assert_eq!(parse_gain(""), None);
+    }
+
+    #[test]
+    fn test_analysis_comment_round_trips_next_to_other_lines() {
+        let entry = CacheEntry {
+            bitrate: Some(245),
+            is_lossless: Some(false),
+            note: None,
+            cutoff_hz: Some(19_500),
+            analyzer_version: Some("1.1.0".into()),
+            analysis_offset: Some(0),
+            analysis_window: Some(30),
+            ..Default::default()
+        };
+        let line = format_analysis(&entry);
+        assert_eq!(
+            line,
+            "KESON_ANALYSIS=bitrate=245;cutoff=19500;lossless=0;version=1.1.0;offset=0;window=30"
+        );
+
+        let comment = format!("Ripped by me\n{}\nKESON_REPLACED=2024-01-01 10:00:00", line);
+        let parsed = parse_analysis(&comment).unwrap();
+        assert_eq!(parsed.bitrate, Some(245));
+        assert_eq!(parsed.cutoff_hz, Some(19_500));
+        assert_eq!(parsed.is_lossless, Some(false));
+        assert_eq!(parsed.analyzer_version.as_deref(), Some("1.1.0"));
+        assert_eq!((parsed.analysis_offset, parsed.analysis_window), (Some(0), Some(30)));
+        assert!(parse_analysis("Ripped by me").is_none());
+    }
+
+    #[test]
+    fn test_comment_line_is_replaced_or_appended() {
+        let comment = "Ripped by me\nKESON_SOURCE=from=soundcloud;url=https://a";
+        assert_eq!(
+            with_comment_line(comment, SOURCE_TAG_KEY, "KESON_SOURCE=from=tidal;url=https://b".into()),
+            "Ripped by me\nKESON_SOURCE=from=tidal;url=https://b"
+        );
+        assert_eq!(
+            with_comment_line("Ripped by me", SOURCE_TAG_KEY, "KESON_SOURCE=from=tidal;url=x".into()),
+            "Ripped by me\nKESON_SOURCE=from=tidal;url=x"
+        );
+        assert_eq!(with_comment_line("", SOURCE_TAG_KEY, "KESON_SOURCE=x".into()), "KESON_SOURCE=x");
     }
 }
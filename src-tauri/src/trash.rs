@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+/// Move a file to the OS trash / recycle bin, so it can be restored from the usual
+/// place. Returns where it ended up when the platform lets us find out.
+pub fn move_to_trash(path: &Path) -> Result<Option<PathBuf>, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Fichier introuvable: {}", e))?;
+    ::trash::delete(&path).map_err(|e| format!("Impossible de déplacer vers la corbeille: {}", e))?;
+
+    log::info!("[trash] Moved {:?} to the trash", path);
+    Ok(find_in_trash(&path))
+}
+
+/// Locate a freshly trashed file by its original path, keeping the newest deletion
+/// if it was trashed before
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn find_in_trash(original: &Path) -> Option<PathBuf> {
+    let items = ::trash::os_limited::list()
+        .map_err(|e| log::warn!("[trash] Cannot list the trash: {}", e))
+        .ok()?;
+    let item = items
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)?;
+    trashed_location(&item)
+}
+
+/// The platform doesn't let us look inside its trash
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn find_in_trash(_original: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// On Windows the item id is its parsing path inside the recycle bin
+#[cfg(target_os = "windows")]
+fn trashed_location(item: &::trash::TrashItem) -> Option<PathBuf> {
+    Some(PathBuf::from(&item.id))
+}
+
+/// On freedesktop systems the item id is its `info/<name>.trashinfo` file, and the
+/// file itself sits in the sibling `files/` directory
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+fn trashed_location(item: &::trash::TrashItem) -> Option<PathBuf> {
+    let info = Path::new(&item.id);
+    Some(info.parent()?.parent()?.join("files").join(info.file_stem()?))
+}
@@ -19,6 +19,9 @@ pub struct DownloadResult {
     pub quality: String,
     pub warning: String,
     pub saved_to: String,
+    /// Tags read back from the file after tagging it; None if it couldn't be tagged
+    #[serde(default)]
+    pub tags: Option<ExtractedMetadata>,
 }
 
 #[derive(Serialize)]
@@ -29,28 +32,270 @@ pub struct RedownloadResult {
     pub new_duration: Option<f64>,
     pub cover_url: Option<String>,
     pub new_bitrate: Option<u32>,
+    /// Set when the download wasn't better than the original, which was left untouched
+    pub warning: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct ScanResult {
     pub path: String,
     pub name: String,
     pub bitrate: Option<u32>,
     pub is_lossless: Option<bool>,
     pub note: Option<String>,
-    pub status: String, // "ok" | "bad" | "error" | "replaced"
+    pub status: ScanStatus,
     pub replaced: bool, // true if KESON_REPLACED tag exists
+    pub size_bytes: Option<u64>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Bitrate declared by the container (ffprobe), in kbps
+    pub container_bitrate: Option<u32>,
+    /// Bits per sample of the decoded stream (16, 24...), when the codec has one
+    #[serde(default)]
+    pub bit_depth: Option<u32>,
+    /// Whether the peak reaches 0 dBFS; None unless `detect_clipping` is on
+    #[serde(default)]
+    pub clipping: Option<bool>,
+    /// Whether the KESON_REPLACED tag can be written to this format
+    #[serde(default)]
+    pub taggable: bool,
+    /// Typed issues met while analyzing; `note` keeps the readable summary
+    #[serde(default)]
+    pub warnings: Vec<ScanWarning>,
+    /// Whether the analysis was answered from the cache
+    #[serde(default)]
+    pub cached: bool,
+    /// Whether the cache was looked up without a usable entry; only counted by the
+    /// scan telemetry, never sent or saved
+    #[serde(skip)]
+    pub cache_missed: bool,
+    /// REPLAYGAIN_TRACK_GAIN tag, in dB
+    #[serde(default)]
+    pub replaygain_track_gain: Option<f64>,
+    /// REPLAYGAIN_ALBUM_GAIN tag, in dB
+    #[serde(default)]
+    pub replaygain_album_gain: Option<f64>,
+    /// Content hash used as cache key, when the cache is on; lets diff_scans follow renames
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+/// Outcome of one scanned file, serialized as the lowercase strings the frontend uses
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStatus {
+    /// At or above the bitrate threshold, or lossless
+    Ok,
+    /// Lossy and below the bitrate threshold
+    Bad,
+    Error,
+    /// Below the threshold but tagged KESON_REPLACED
+    Replaced,
+    /// Listed by a dry run, not analyzed yet
+    #[default]
+    Pending,
+    /// Left out without analysis (too small, no audio stream)
+    Skipped,
+    /// Not analyzed because the scan was cancelled first
+    Cancelled,
+}
+
+impl ScanStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanStatus::Ok => "ok",
+            ScanStatus::Bad => "bad",
+            ScanStatus::Error => "error",
+            ScanStatus::Replaced => "replaced",
+            ScanStatus::Pending => "pending",
+            ScanStatus::Skipped => "skipped",
+            ScanStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the analyzer (or its cache) produced an estimate for the file
+    pub fn is_analyzed(self) -> bool {
+        matches!(self, ScanStatus::Ok | ScanStatus::Bad | ScanStatus::Replaced)
+    }
+}
+
+/// What kind of problem a scan warning reports, so the UI can group them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanWarningCode {
+    /// No whatsmybitrate binary or script could be found
+    AnalyzerMissing,
+    /// The python analyzer can't import its requirements
+    AnalyzerDepsMissing,
+    /// The analyzer ran but failed or rejected the file (often a corrupt file)
+    AnalyzerFailed,
+    /// The analyzer finished without a bitrate estimate
+    NoBitrate,
+    /// ffprobe couldn't read the stream, so codec and container figures are missing
+    ProbeUnavailable,
+    /// The file couldn't be hashed, so the cache was bypassed
+    HashFailed,
+    /// The container bitrate disagrees sharply with the analyzed one
+    BitrateMismatch,
+    /// The file is empty, too small to hold audio, or has no duration: not analyzed
+    TruncatedFile,
+    Cancelled,
+    /// Emitted once per scan as "scan_warning": the root holds far more other files
+    /// than audio, probably a home or system folder picked by mistake
+    BroadRoot,
+    /// Emitted once per scan as "scan_warning": folders or files the walk couldn't
+    /// read (permissions, broken links) were left out
+    UnreadableEntries,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScanWarning {
+    pub code: ScanWarningCode,
+    pub message: String,
+}
+
+impl ScanWarning {
+    pub fn new(code: ScanWarningCode, message: impl Into<String>) -> Self {
+        ScanWarning {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Detailed scan progress, emitted as `scan_progress_detail` alongside the bare percent
+#[derive(Serialize, Clone, Debug)]
+pub struct ScanProgress {
+    pub percent: u32,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub elapsed_secs: f64,
+    pub eta_secs: Option<f64>,
+}
+
+/// Emitted as "scan_telemetry" about once a second while a scan analyzes files, to
+/// help tune rayon_threads and max_concurrent_analyses
+#[derive(Serialize, Clone, Debug)]
+pub struct ScanTelemetry {
+    pub files_done: usize,
+    pub files_per_sec: f64,
+    /// Files answered from the cache, and files looked up in it without a usable entry
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    /// Analyzer processes running right now, out of `analyzer_slots`
+    pub active_analyzers: usize,
+    pub analyzer_slots: usize,
+}
+
+/// One file's outcome in mark_replaced
+#[derive(Serialize, Clone, Debug)]
+pub struct TagWriteOutcome {
+    pub path: String,
+    pub tagged: bool,
+    pub error: Option<String>,
+}
+
+/// compute_replaygain's result for one file
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ReplayGainOutcome {
+    pub path: String,
+    /// Gain written as REPLAYGAIN_TRACK_GAIN, in dB
+    pub track_gain: Option<f64>,
+    /// Gain written as REPLAYGAIN_ALBUM_GAIN, in dB (album mode only)
+    pub album_gain: Option<f64>,
+    pub tagged: bool,
+    pub error: Option<String>,
+}
+
+/// verify_replaced's audit of KESON_REPLACED tags against the analysis cache
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ReplacedAudit {
+    pub tagged: Vec<String>,
+    pub untagged: Vec<String>,
+    /// Tagged files whose cached analysis is still under the bitrate threshold,
+    /// i.e. a replacement that didn't actually improve anything
+    pub tagged_but_bad: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+/// Per-file progress of a batch operation, `done` out of `total` after `path`
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchProgress {
+    pub done: usize,
+    pub total: usize,
+    pub path: String,
+}
+
+/// Raw outcome of one analyzer run, returned by diagnose_analysis for support
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct AnalyzerDiagnostics {
+    /// "binary" or "python"
+    pub analyzer: &'static str,
+    pub program: String,
+    /// Full command line, program first
+    pub argv: Vec<String>,
+    /// None when killed by a signal or never started
+    pub exit_code: Option<i32>,
+    /// Signal or crash code, when the process didn't exit on its own
+    pub abnormal_exit: Option<String>,
+    /// Why the process could not be started
+    pub spawn_error: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// stdout as JSON, when it parses
+    pub parsed: Option<serde_json::Value>,
+    pub elapsed_ms: u64,
+}
+
+/// Payload of "analyzer_warming": `warming` is true while the session's first analyzer
+/// call is running, false once it has returned
+#[derive(Serialize, Clone, Debug)]
+pub struct AnalyzerWarming {
+    pub warming: bool,
+    /// "binary" or "python"
+    pub analyzer: &'static str,
+}
+
+/// A folder that could not be opened by reveal_many
+#[derive(Serialize, Clone, Debug)]
+pub struct RevealFailure {
+    pub path: String,
+    pub error: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct CacheEntry {
     pub bitrate: Option<u32>,
     pub is_lossless: Option<bool>,
     pub note: Option<String>,
+    #[serde(default)]
+    pub cutoff_hz: Option<u32>,
+    /// Analyzer version that produced the entry; None for entries written before versioning
+    #[serde(default)]
+    pub analyzer_version: Option<String>,
+    /// Last path the entry was seen at; None for entries written before paths were kept
+    #[serde(default)]
+    pub path: Option<String>,
+    /// ffprobe figures of the file, so cache hits don't run ffprobe again; None when
+    /// the probe failed or for entries written before they were kept
+    #[serde(default)]
+    pub stream: Option<StreamInfo>,
+    /// analysis_offset_seconds and the window, in seconds, the analysis covered
+    #[serde(default)]
+    pub analysis_offset: Option<u32>,
+    #[serde(default)]
+    pub analysis_window: Option<u32>,
+    /// Whether the peak reaches 0 dBFS; None until a scan with `detect_clipping` on
+    /// has checked the file
+    #[serde(default)]
+    pub clipping: Option<bool>,
 }
 
 /// Metadata extracted from an audio file using ffprobe
-#[derive(Serialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ExtractedMetadata {
     pub artist: Option<String>,
     pub title: Option<String>,
@@ -59,6 +304,91 @@ pub struct ExtractedMetadata {
     pub isrc: Option<String>,
 }
 
+/// Audio stream properties reported by ffprobe
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StreamInfo {
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Format-level bit_rate, in kbps
+    pub container_bitrate: Option<u32>,
+    /// bits_per_raw_sample, or bits_per_sample for PCM; lossy codecs report neither
+    pub bit_depth: Option<u32>,
+    /// Format-level duration, in seconds
+    pub duration: Option<f64>,
+    /// Audio streams in the file; None when ffprobe couldn't read it
+    #[serde(default)]
+    pub audio_streams: Option<u32>,
+}
+
+/// Estimate for one analysis window, returned by analyze_windows
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct WindowAnalysis {
+    pub window_seconds: u32,
+    pub bitrate: Option<u32>,
+    pub is_lossless: Option<bool>,
+    pub cutoff_hz: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Resolution status of one external tool
+#[derive(Serialize, Clone, Debug)]
+pub struct ToolStatus {
+    pub found: bool,
+    pub path: Option<String>,
+    /// First line of the tool's version output, when it has one
+    pub version: Option<String>,
+}
+
+/// External tools the analysis pipeline depends on
+#[derive(Serialize, Clone, Debug)]
+pub struct DependencyReport {
+    pub ffprobe: ToolStatus,
+    pub ffmpeg: ToolStatus,
+    pub whatsmybitrate: ToolStatus,
+    /// Analyzer picked under the `analyzer_mode` setting, "binary" or "python"
+    pub analyzer: Option<&'static str>,
+}
+
+/// Versions of the external tools, for bug reports and cache versioning
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ToolVersions {
+    /// First line of `ffprobe -version`
+    pub ffprobe: Option<String>,
+    /// First line of `ffmpeg -version`
+    pub ffmpeg: Option<String>,
+    /// whatsmybitrate's ANALYZER_VERSION
+    pub whatsmybitrate: Option<String>,
+}
+
+/// Embedded cover art, ready to use as an <img> src
+#[derive(Serialize, Clone, Debug)]
+pub struct CoverArt {
+    /// `data:<mime>;base64,...`
+    pub data_url: String,
+    pub mime_type: String,
+}
+
+/// Optional spectrogram rendering overrides; `None` keeps the analyzer default
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SpectrogramOptions {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Top of the frequency axis, in kHz (capped to the file's Nyquist frequency)
+    pub max_freq_khz: Option<f64>,
+}
+
+/// Analysis and spectrogram of one file, produced by a single analyzer run
+#[derive(Serialize, Clone, Debug)]
+pub struct FileInspection {
+    pub path: String,
+    pub bitrate: Option<u32>,
+    pub is_lossless: Option<bool>,
+    pub cutoff_hz: Option<u32>,
+    /// PNG in the app cache dir, shown through the asset protocol
+    pub spectrogram_path: String,
+}
+
 /// Search result from Tidal or SoundCloud
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SearchResult {
@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use notify_debouncer_full::notify::{EventKind, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult};
+use tauri::{Emitter, Manager};
+use walkdir::WalkDir;
+
+use crate::audio::{is_audio, is_hidden};
+use crate::cache::{cache_path, load_cache, lock_cache, save_cache, ActiveCaches};
+use crate::scan_state::ScanTracker;
+use crate::settings::load_settings;
+use crate::types::ScanStatus;
+
+/// Quiet period before a burst of filesystem events on a file is reported
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Delay between two size/mtime checks of a changed file before it counts as written
+const SETTLE_INTERVAL: Duration = Duration::from_secs(2);
+/// Granularity of stop-flag checks while waiting for events
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Active folder watchers keyed by folder path, each with its stop flag
+#[derive(Default)]
+pub struct WatcherState(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl WatcherState {
+    /// Signal every watcher thread to exit (used on app shutdown)
+    pub fn stop_all(&self) {
+        let mut watchers = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, stop) in watchers.drain() {
+            stop.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Modification time and size of changed audio files
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+/// Whether a path reported by the watcher is an audio file a scan of `root` would pick
+/// up, and not a redownload of ours waiting to be checked
+fn is_candidate(root: &Path, path: &Path, extensions: &[String], skip_hidden: bool) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    if relative.components().any(|c| c.as_os_str() == "backup-ksi") || !is_audio(path, extensions) {
+        return false;
+    }
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    if name.starts_with(crate::CANDIDATE_PREFIX) {
+        return false;
+    }
+    if skip_hidden {
+        if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+            return false;
+        }
+        let entry = WalkDir::new(path).max_depth(0).into_iter().next();
+        if entry.and_then(|e| e.ok()).is_some_and(|e| is_hidden(&e)) {
+            return false;
+        }
+    }
+    true
+}
+
+fn signatures<'a>(paths: impl Iterator<Item = &'a PathBuf>) -> Snapshot {
+    paths
+        .filter_map(|path| {
+            let meta = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+            Some((path.clone(), (meta.modified().ok()?, meta.len())))
+        })
+        .collect()
+}
+
+/// Return the changed files whose size and mtime haven't moved since the previous
+/// check, so files still being written (downloads, our own tag writes) wait. Files
+/// that disappeared are dropped.
+fn settle(pending: &mut Snapshot, current: &Snapshot) -> Vec<PathBuf> {
+    let mut ready = Vec::new();
+    for (path, sig) in current {
+        if pending.get(path) == Some(sig) {
+            pending.remove(path);
+            ready.push(path.clone());
+        } else {
+            pending.insert(path.clone(), *sig);
+        }
+    }
+    pending.retain(|path, _| current.contains_key(path));
+    ready
+}
+
+/// Analyze settled files, returning them all for later when a scan is running. The
+/// scan slot is held for the whole batch, so no scan can start and save its cache
+/// over the watcher's (or the other way round) until the batch is done.
+fn analyze_changed(app: &tauri::AppHandle, paths: &[PathBuf], current: &Snapshot) -> Vec<PathBuf> {
+    let tracker = app.state::<ScanTracker>();
+    let active = app.state::<ActiveCaches>();
+    let Some(_scan) = tracker.try_start() else {
+        return paths.to_vec();
+    };
+    if active.is_busy() {
+        return paths.to_vec();
+    }
+
+    let settings = load_settings(app);
+    let cache_path = match cache_path(app) {
+        Ok(p) => p,
+        Err(e) => {
+            log::error!("[watch] Cannot resolve cache path: {}", e);
+            return Vec::new();
+        }
+    };
+    let cache = Arc::new(Mutex::new(load_cache(&cache_path, settings.cache_max_entries)));
+    active.register(&cache_path, &cache);
+
+    let mut deferred = Vec::new();
+    for path in paths {
+        let (min, window) = settings.thresholds_for(path, None, None);
+        log::info!("[watch] Analyzing changed file: {:?}", path);
+        let result = crate::scan_file(
+            path,
+            current.get(path).map(|(_, size)| *size),
+            app,
+            min,
+            window,
+            &settings,
+            &cache,
+        );
+        // Hashing stopped by a cancel_scan while the batch held the scan slot
+        if result.status == ScanStatus::Cancelled {
+            deferred.push(path.clone());
+            continue;
+        }
+        let _ = app.emit("scan_result", result);
+    }
+
+    let _ = save_cache(&cache_path, &lock_cache(&cache));
+    active.unregister(&cache);
+    deferred
+}
+
+fn watch_loop(app: tauri::AppHandle, root: PathBuf, stop: Arc<AtomicBool>) {
+    // Events carry resolved paths (/private/var/... on macOS), which a symlinked root
+    // would never prefix
+    let root = root.canonicalize().unwrap_or(root);
+    let settings = load_settings(&app);
+    let (extensions, skip_hidden) = (settings.scan_extensions(), settings.skip_hidden);
+
+    let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = match new_debouncer(DEBOUNCE_TIMEOUT, None, tx) {
+        Ok(d) => d,
+        Err(e) => {
+            log::error!("[watch] Cannot create watcher for {:?}: {}", root, e);
+            return;
+        }
+    };
+    if let Err(e) = debouncer.watch(&root, RecursiveMode::Recursive) {
+        log::error!("[watch] Cannot watch {:?}: {}", root, e);
+        return;
+    }
+    log::info!("[watch] Watching {:?}", root);
+
+    let mut changed = HashSet::new();
+    let mut pending = Snapshot::new();
+    let mut last_check = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        match rx.recv_timeout(STOP_CHECK_INTERVAL) {
+            Ok(Ok(events)) => {
+                let paths = events
+                    .into_iter()
+                    .filter(|e| matches!(e.kind, EventKind::Create(_) | EventKind::Modify(_)))
+                    .flat_map(|e| e.event.paths);
+                // Our own tag writes (mark_replaced, embed_analysis, ReplayGain,
+                // download tags) are not changes worth a new analysis
+                changed.extend(paths.filter(|p| {
+                    is_candidate(&root, p, &extensions, skip_hidden)
+                        && !crate::tagging::written_recently(p)
+                }));
+            }
+            Ok(Err(errors)) => {
+                for e in errors {
+                    log::warn!("[watch] Watcher error on {:?}: {}", root, e);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if (changed.is_empty() && pending.is_empty()) || last_check.elapsed() < SETTLE_INTERVAL {
+            continue;
+        }
+        last_check = Instant::now();
+
+        let current = signatures(changed.iter().chain(pending.keys()));
+        let ready = settle(&mut pending, &current);
+        changed.clear();
+        if ready.is_empty() {
+            continue;
+        }
+        let deferred = analyze_changed(&app, &ready, &current);
+        if !deferred.is_empty() {
+            log::info!("[watch] Scan in progress, deferring {} file(s)", deferred.len());
+            changed.extend(deferred);
+        }
+    }
+    log::info!("[watch] Stopped watching {:?}", root);
+}
+
+/// Watch a folder and analyze audio files as they are created or modified,
+/// emitting a `scan_result` event for each
+#[tauri::command]
+pub fn watch_folder(
+    path: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, WatcherState>,
+) -> Result<(), String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err("Dossier introuvable".into());
+    }
+
+    let mut watchers = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    if watchers.contains_key(&path) {
+        return Ok(());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    watchers.insert(path, stop.clone());
+    std::thread::spawn(move || watch_loop(app, root, stop));
+    Ok(())
+}
+
+/// Stop watching a folder. Returns false if it wasn't being watched.
+#[tauri::command]
+pub fn unwatch_folder(path: String, state: tauri::State<'_, WatcherState>) -> bool {
+    let mut watchers = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    match watchers.remove(&path) {
+        Some(stop) => {
+            stop.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settle_waits_for_a_stable_signature() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+        let file = PathBuf::from("/music/new.mp3");
+        let mut pending = Snapshot::new();
+
+        // Still being written: size changes between checks
+        let check1 = Snapshot::from([(file.clone(), (t0, 100))]);
+        assert!(settle(&mut pending, &check1).is_empty());
+        let check2 = Snapshot::from([(file.clone(), (t1, 200))]);
+        assert!(settle(&mut pending, &check2).is_empty());
+
+        // Unchanged since last check: ready once, then no longer pending
+        assert_eq!(settle(&mut pending, &check2), vec![file.clone()]);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_settle_drops_deleted_files() {
+        let file = PathBuf::from("/music/gone.mp3");
+        let mut pending = Snapshot::from([(file, (SystemTime::UNIX_EPOCH, 100))]);
+        assert!(settle(&mut pending, &Snapshot::new()).is_empty());
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_is_candidate_filters_like_a_scan() {
+        let root = Path::new("/music");
+        let ext = vec!["mp3".to_string()];
+        assert!(is_candidate(root, Path::new("/music/a/track.mp3"), &ext, true));
+        assert!(!is_candidate(root, Path::new("/music/a/cover.jpg"), &ext, true));
+        assert!(!is_candidate(root, Path::new("/music/backup-ksi/track.mp3"), &ext, true));
+        assert!(!is_candidate(root, Path::new("/music/.hidden/track.mp3"), &ext, true));
+        assert!(is_candidate(root, Path::new("/music/.hidden/track.mp3"), &ext, false));
+        assert!(!is_candidate(root, Path::new("/other/track.mp3"), &ext, true));
+        assert!(!is_candidate(root, Path::new("/music/a/ksi-candidate-track.mp3"), &ext, true));
+    }
+}
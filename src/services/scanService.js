@@ -10,9 +10,152 @@ export async function pickFolderDialog() {
   return typeof choice === 'string' ? choice : null
 }
 
-export async function scanFolder(folder, minKbps = 256) {
+export async function scanFolder(folder, minKbps = null, options = {}) {
   if (!isDesktop) throw new Error('Scan disponible seulement en mode desktop')
-  return invoke('scan_folder', { folder, minKbps })
+  return invoke('scan_folder', {
+    folder,
+    minKbps,
+    followSymlinks: options.followSymlinks ?? false,
+    minBytes: options.minBytes ?? null,
+    maxBytes: options.maxBytes ?? null
+  })
+}
+
+export async function analyzeFiles(paths, minKbps, window) {
+  if (!isDesktop) throw new Error('Scan disponible seulement en mode desktop')
+  return invoke('analyze_files', { paths, minKbps: minKbps ?? null, window: window ?? null })
+}
+
+export async function countScanCandidates(folder, options = {}) {
+  if (!isDesktop) throw new Error('Scan disponible seulement en mode desktop')
+  return invoke('scan_folder', {
+    folder,
+    countOnly: true,
+    followSymlinks: options.followSymlinks ?? false,
+    minBytes: options.minBytes ?? null,
+    maxBytes: options.maxBytes ?? null
+  })
+}
+
+export async function analyzeWindows(path, windows) {
+  if (!isDesktop) throw new Error('Analyse disponible seulement en mode desktop')
+  return invoke('analyze_windows', { path, windows })
+}
+
+export async function analyzeFileJson(path) {
+  if (!isDesktop) return null
+  return invoke('analyze_file_json', { path })
+}
+
+export async function diagnoseAnalysis(path) {
+  if (!isDesktop) return null
+  return invoke('diagnose_analysis', { path })
+}
+
+export async function checkDependencies() {
+  if (!isDesktop) return null
+  return invoke('check_dependencies')
+}
+
+export async function toolVersions() {
+  if (!isDesktop) return null
+  return invoke('tool_versions')
+}
+
+export async function queryResults(query = {}) {
+  if (!isDesktop) throw new Error('Scan disponible seulement en mode desktop')
+  return invoke('query_results', { query })
+}
+
+export async function computeHistogram(results, bucketSizeKbps = 32) {
+  if (!isDesktop) return []
+  return invoke('compute_histogram', { results, bucketSizeKbps })
+}
+
+export async function diffScans(before, after) {
+  if (!isDesktop) return null
+  return invoke('diff_scans', { before, after })
+}
+
+export async function libraryStats() {
+  if (!isDesktop) return null
+  return invoke('library_stats')
+}
+
+export async function pruneCache() {
+  if (!isDesktop) return 0
+  return invoke('prune_cache')
+}
+
+export async function findDuplicates(results) {
+  if (!isDesktop) return []
+  return invoke('find_duplicates', { results })
+}
+
+export async function compareFiles(paths) {
+  if (!isDesktop) return null
+  return invoke('compare_files', { paths })
+}
+
+export async function qualityReference(isrc, path = null) {
+  if (!isDesktop) return null
+  return invoke('quality_reference', { isrc, path })
+}
+
+export async function loadLastScan() {
+  if (!isDesktop) return null
+  return invoke('load_last_scan')
+}
+
+export async function probeFile(path) {
+  if (!isDesktop) throw new Error('Analyse disponible seulement en mode desktop')
+  return invoke('probe_file', { path })
+}
+
+export async function probeDurations(paths) {
+  if (!isDesktop) return {}
+  return invoke('probe_durations', { paths })
+}
+
+export async function scanSummary(folder, minKbps = null, options = {}) {
+  if (!isDesktop) return null
+  return invoke('scan_summary', {
+    folder,
+    minKbps,
+    followSymlinks: options.followSymlinks ?? false,
+    minBytes: options.minBytes ?? null,
+    maxBytes: options.maxBytes ?? null
+  })
+}
+
+export async function cancelScan() {
+  if (!isDesktop) return false
+  return invoke('cancel_scan')
+}
+
+export async function scanState() {
+  if (!isDesktop) return null
+  return invoke('scan_state')
+}
+
+export async function estimateScan(folder, options = {}) {
+  if (!isDesktop) return null
+  return invoke('estimate_scan', {
+    folder,
+    followSymlinks: options.followSymlinks ?? false,
+    minBytes: options.minBytes ?? null,
+    maxBytes: options.maxBytes ?? null
+  })
+}
+
+export async function reanalyzeFile(path) {
+  if (!isDesktop) return null
+  return invoke('reanalyze_file', { path })
+}
+
+export async function retryErrors(minKbps) {
+  if (!isDesktop) return []
+  return invoke('retry_errors', { minKbps: minKbps ?? null })
 }
 
 export async function revealInFolder(path) {
@@ -20,13 +163,34 @@ export async function revealInFolder(path) {
   return invoke('reveal_in_folder', { path })
 }
 
-export async function openSpectrum(path) {
+export async function openContainingFolder(path) {
+  if (!isDesktop) return
+  return invoke('open_containing_folder', { path })
+}
+
+export async function revealMany(paths) {
+  if (!isDesktop) return []
+  return invoke('reveal_many', { paths })
+}
+
+export async function openSpectrum(path, options = {}) {
   if (!isDesktop) throw new Error('Spectre disponible seulement en mode desktop')
-  const bytes = await invoke('open_spectrum', { path })
+  const bytes = await invoke('open_spectrum', {
+    path,
+    width: options.width ?? null,
+    height: options.height ?? null,
+    maxFreqKhz: options.maxFreqKhz ?? null
+  })
   const blob = new Blob([new Uint8Array(bytes)], { type: 'image/png' })
   return URL.createObjectURL(blob)
 }
 
+export async function inspectFile(path) {
+  if (!isDesktop) throw new Error('Inspection disponible seulement en mode desktop')
+  const inspection = await invoke('inspect_file', { path })
+  return { ...inspection, spectrogramUrl: convertFileSrc(inspection.spectrogram_path) }
+}
+
 export async function redownloadBad(paths, options = {}) {
   if (!isDesktop) throw new Error('Disponible seulement en desktop')
   return invoke('redownload_bad', {
@@ -45,6 +209,11 @@ export async function downloadWithUrl(originalPath, url, backup = true) {
   })
 }
 
+export async function downloadFromClipboard() {
+  if (!isDesktop) throw new Error('Disponible seulement en desktop')
+  return invoke('download_from_clipboard')
+}
+
 export async function acceptRedownload(original, fresh) {
   if (!isDesktop) throw new Error('Disponible seulement en desktop')
   return invoke('accept_redownload', { original, newPath: fresh })
@@ -55,6 +224,16 @@ export async function discardFile(path) {
   return invoke('discard_file', { path })
 }
 
+export async function trashFile(path) {
+  if (!isDesktop) return null
+  return invoke('trash_file', { path })
+}
+
+export async function organizeFile(path, root, template = '{artist}/{album}/{title}') {
+  if (!isDesktop) return null
+  return invoke('organize_file', { path, root, template })
+}
+
 export async function revertReplacement(originalPath) {
   if (!isDesktop) return false
   return invoke('revert_replacement', { originalPath })
@@ -68,11 +247,123 @@ export async function listenScanProgress(callback) {
   return unlisten
 }
 
+export async function listenScanProgressDetail(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('scan_progress_detail', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function listenScanWarning(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('scan_warning', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function listenScanTelemetry(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('scan_telemetry', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function listenAnalyzerWarming(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('analyzer_warming', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function watchFolder(path) {
+  if (!isDesktop) return
+  return invoke('watch_folder', { path })
+}
+
+export async function unwatchFolder(path) {
+  if (!isDesktop) return false
+  return invoke('unwatch_folder', { path })
+}
+
+export async function listenScanResult(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('scan_result', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function listenScanFailed(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('scan_failed', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function installPythonDeps() {
+  if (!isDesktop) return
+  return invoke('install_python_deps')
+}
+
+export async function listenPipInstallProgress(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('pip_install_progress', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function markReplaced(paths) {
+  if (!isDesktop) return []
+  return invoke('mark_replaced', { paths })
+}
+
+export async function verifyReplaced(paths) {
+  if (!isDesktop) return null
+  return invoke('verify_replaced', { paths })
+}
+
+export async function listenMarkReplacedProgress(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('mark_replaced_progress', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
+export async function computeReplaygain(paths, albumMode = false) {
+  if (!isDesktop) return []
+  return invoke('compute_replaygain', { paths, albumMode })
+}
+
+export async function listenReplaygainProgress(callback) {
+  if (!isDesktop) return null
+  const unlisten = await listen('replaygain_progress', (event) => {
+    callback(event?.payload)
+  })
+  return unlisten
+}
+
 export async function extractCover(audioPath) {
   if (!isDesktop) return null
   return invoke('extract_cover', { audioPath })
 }
 
+export async function writeTags(path, metadata) {
+  if (!isDesktop) return false
+  return invoke('write_tags', { path, metadata })
+}
+
+export async function writeAnalysisTag(path, { bitrate, cutoff, isLossless } = {}) {
+  if (!isDesktop) return false
+  return invoke('write_analysis_tag', { path, bitrate, cutoff, isLossless })
+}
+
 /**
  * Convert a local file path to a Tauri asset URL for use in src attributes.
  * Handles both Unix paths (/) and Windows paths (C:\).
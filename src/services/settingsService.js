@@ -12,3 +12,13 @@ export async function persistSettings(settings) {
   if (!isDesktop) return null
   return invoke('save_settings', { settings })
 }
+
+export async function fetchDataDir() {
+  if (!isDesktop) return null
+  return invoke('get_data_dir')
+}
+
+export async function openDataDir() {
+  if (!isDesktop) return
+  return invoke('open_data_dir')
+}